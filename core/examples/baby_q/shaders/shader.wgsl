@@ -16,12 +16,61 @@ struct Light {
 @group(2) @binding(0)
 var<uniform> light: Light;
 
+// Packed chunk vertex, see `vertex::ChunkVertex`
 struct VertexInput {
-    @location(0) position: vec3<f32>,
-    @location(1) tex_coords: vec2<f32>,
-    @location(2) normal: vec3<f32>,
-    @location(3) tangent: vec3<f32>,
-    @location(4) bitangent: vec3<f32>,
+    @location(0) data: vec2<u32>,
+}
+struct ChunkVertex {
+    position: vec3<f32>,
+    tex_coords: vec2<f32>,
+    normal: vec3<f32>,
+    tangent: vec3<f32>,
+    bitangent: vec3<f32>,
+    // 0.0 is a fully occluded corner, 1.0 is an open one
+    ao: f32,
+    // layer of the block texture arrays
+    material: u32,
+}
+fn decode_vertex(data: vec2<u32>) -> ChunkVertex {
+    // faces in `Voxel::FACES` order: back, front, top, bottom, left, right
+    var normals = array<vec3<f32>, 6>(
+        vec3<f32>(0.0, 0.0, -1.0),
+        vec3<f32>(0.0, 0.0, 1.0),
+        vec3<f32>(0.0, 1.0, 0.0),
+        vec3<f32>(0.0, -1.0, 0.0),
+        vec3<f32>(-1.0, 0.0, 0.0),
+        vec3<f32>(1.0, 0.0, 0.0),
+    );
+    var tangents = array<vec3<f32>, 6>(
+        vec3<f32>(1.0, 0.0, 0.0),
+        vec3<f32>(-1.0, 0.0, 0.0),
+        vec3<f32>(-1.0, 0.0, 0.0),
+        vec3<f32>(-1.0, 0.0, 0.0),
+        vec3<f32>(0.0, 0.0, -1.0),
+        vec3<f32>(0.0, 0.0, 1.0),
+    );
+    var bitangents = array<vec3<f32>, 6>(
+        vec3<f32>(0.0, 1.0, 0.0),
+        vec3<f32>(0.0, 1.0, 0.0),
+        vec3<f32>(0.0, 0.0, -1.0),
+        vec3<f32>(0.0, 0.0, 1.0),
+        vec3<f32>(0.0, 1.0, 0.0),
+        vec3<f32>(0.0, 1.0, 0.0),
+    );
+    let face = (data.x >> 17u) & 7u;
+    var out: ChunkVertex;
+    out.position = vec3<f32>(
+        f32(data.x & 31u),
+        f32((data.x >> 5u) & 127u),
+        f32((data.x >> 12u) & 31u),
+    );
+    out.tex_coords = vec2<f32>(f32(data.y & 127u), f32((data.y >> 7u) & 127u));
+    out.normal = normals[face];
+    out.tangent = tangents[face];
+    out.bitangent = bitangents[face];
+    out.ao = f32((data.x >> 20u) & 3u) / 3.0;
+    out.material = data.y >> 16u;
+    return out;
 }
 struct InstanceInput {
     @location(5) model_matrix_0: vec4<f32>,
@@ -41,13 +90,15 @@ struct VertexOutput {
     @location(3) tangent_view_position: vec3<f32>,
     @location(4) world_position: vec4<f32>,
     @location(5) shadow_pos: vec3<f32>,
+    @location(6) @interpolate(flat) material: u32,
 }
 
 @vertex
 fn vs_main(
-    model: VertexInput,
+    vertex: VertexInput,
     instance: InstanceInput,
 ) -> VertexOutput {
+    let model = decode_vertex(vertex.data);
     let model_matrix = mat4x4<f32>(
         instance.model_matrix_0,
         instance.model_matrix_1,
@@ -76,6 +127,7 @@ fn vs_main(
     out.world_position = world_position;
     out.clip_position = camera.view_proj * world_position;
     out.tex_coords = model.tex_coords;
+    out.material = model.material;
     out.tangent_position = tangent_matrix * world_position.xyz;
     out.tangent_view_position = tangent_matrix * camera.view_pos.xyz;
     out.tangent_light_position = tangent_matrix * light.view_pos.xyz;
@@ -86,15 +138,15 @@ fn vs_main(
 
 // Fragment shader
 @group(0) @binding(0)
-var t_diffuse: texture_2d<f32>;
+var t_diffuse: texture_2d_array<f32>;
 @group(0)@binding(1)
 var s_diffuse: sampler;
 @group(0)@binding(2)
-var t_normal: texture_2d<f32>;
+var t_normal: texture_2d_array<f32>;
 @group(0) @binding(3)
 var s_normal: sampler;
 @group(0)@binding(4)
-var t_specular: texture_2d<f32>;
+var t_specular: texture_2d_array<f32>;
 @group(0) @binding(5)
 var s_specular: sampler;
 
@@ -142,9 +194,9 @@ fn fetch_shadow(homogeneous_coords: vec4<f32>) -> f32 {
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    let object_color: vec4<f32> = textureSample(t_diffuse, s_diffuse, in.tex_coords);
-    let object_normal: vec4<f32> = textureSample(t_normal, s_normal, in.tex_coords);
-    let object_specular: vec4<f32> = textureSample(t_specular, s_specular, in.tex_coords);
+    let object_color: vec4<f32> = textureSample(t_diffuse, s_diffuse, in.tex_coords, i32(in.material));
+    let object_normal: vec4<f32> = textureSample(t_normal, s_normal, in.tex_coords, i32(in.material));
+    let object_specular: vec4<f32> = textureSample(t_specular, s_specular, in.tex_coords, i32(in.material));
 
 
     let distance_from_light = distance(in.tangent_position, in.tangent_light_position);
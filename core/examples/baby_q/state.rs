@@ -1,13 +1,17 @@
 use super::{camera, light, shadow};
 
-use cgmath::Vector3;
+use core::block::RenderType;
+use core::chunk::buffers::ChunkBuffers;
+use core::chunk::lod::Lod;
 use core::chunk::Chunk;
+use core::coords::ChunkPos;
 use core::instance;
 use core::model;
 use core::model::DrawLight;
-use core::model::DrawModel;
 use core::model::Vertex;
 use core::texture;
+use core::vertex::ChunkVertex;
+use core::world::World;
 use std::iter;
 use winit::event::ElementState;
 use winit::event::KeyboardInput;
@@ -20,10 +24,12 @@ pub struct State {
     pub camera: camera::Camera,
     pub light: light::Light,
     pub chunks: Vec<Chunk>,
+    chunk_buffers: ChunkBuffers,
     depth_texture: texture::Texture,
     pub mouse_pressed: bool,
     render_pipeline: wgpu::RenderPipeline,
-    materials: Vec<model::Material>,
+    /// Texture arrays of all the blocks, a layer per block material
+    block_material: model::Material,
     shadow: shadow::Shadow,
     light_render_pipeline: wgpu::RenderPipeline,
 }
@@ -44,7 +50,7 @@ impl State {
                             visibility: wgpu::ShaderStages::FRAGMENT,
                             ty: wgpu::BindingType::Texture {
                                 multisampled: false,
-                                view_dimension: wgpu::TextureViewDimension::D2,
+                                view_dimension: wgpu::TextureViewDimension::D2Array,
                                 sample_type: wgpu::TextureSampleType::Float { filterable: true },
                             },
                             count: None,
@@ -63,7 +69,7 @@ impl State {
                             ty: wgpu::BindingType::Texture {
                                 multisampled: false,
                                 sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                                view_dimension: wgpu::TextureViewDimension::D2,
+                                view_dimension: wgpu::TextureViewDimension::D2Array,
                             },
                             count: None,
                         },
@@ -79,7 +85,7 @@ impl State {
                             ty: wgpu::BindingType::Texture {
                                 multisampled: false,
                                 sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                                view_dimension: wgpu::TextureViewDimension::D2,
+                                view_dimension: wgpu::TextureViewDimension::D2Array,
                             },
                             count: None,
                         },
@@ -92,17 +98,24 @@ impl State {
                     ],
                     label: Some("texture_bind_group_layout"),
                 });
-        let materials = core::utils::load_materials(&engine, &texture_bind_group_layout);
+        let block_material = core::utils::load_block_material(&engine, &texture_bind_group_layout);
         let mut chunks = vec![];
+        let mut chunk_buffers = ChunkBuffers::new(&engine.device);
+        let seed = 1982;
+        let mut world = World::empty(seed);
         for x in 0..1 {
             for z in 0..1 {
-                let position = Vector3::new(
-                    (x * Chunk::WIDTH) as f32 - 8.0,
-                    0.0,
-                    (z * Chunk::WIDTH) as f32 - 8.0,
-                );
-                let chunk = Chunk::new(position, &engine.device);
-                chunks.push(chunk);
+                let pos = ChunkPos::new(x, z);
+                world.insert_chunk(pos, Chunk::generate(seed, pos));
+                chunks.extend(Chunk::new(
+                    pos,
+                    &world,
+                    Default::default(),
+                    Lod::FULL,
+                    &mut chunk_buffers,
+                    &engine.device,
+                    &engine.queue,
+                ));
             }
         }
         let render_pipeline = {
@@ -127,7 +140,7 @@ impl State {
                 &layout,
                 engine.config.format,
                 Some(texture::Texture::DEPTH_FORMAT),
-                &[model::ModelVertex::desc(), instance::InstanceRaw::desc()],
+                &[ChunkVertex::desc(), instance::InstanceRaw::desc()],
                 shader,
                 Some("Color Render Pipeline"),
             )
@@ -164,11 +177,12 @@ impl State {
             camera,
             light,
             chunks,
+            chunk_buffers,
             depth_texture,
             mouse_pressed: false,
             render_pipeline,
             light_render_pipeline,
-            materials,
+            block_material,
             shadow,
         }
     }
@@ -258,6 +272,20 @@ impl State {
                 .create_command_encoder(&wgpu::CommandEncoderDescriptor {
                     label: Some("Render Encoder"),
                 });
+        // both passes draw every chunk
+        let draws = self
+            .chunks
+            .iter()
+            .filter_map(|chunk| chunk.mesh.as_ref())
+            .flat_map(|mesh| {
+                RenderType::ALL
+                    .into_iter()
+                    .filter_map(|render| self.chunk_buffers.command(mesh, render))
+            })
+            .collect();
+        let [batch] = self
+            .chunk_buffers
+            .batches(&self.engine.device, &self.engine.queue, [draws]);
         {
             let mut shadow_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Shadow render pass"),
@@ -280,16 +308,9 @@ impl State {
                 }),
             });
             shadow_pass.set_pipeline(&self.shadow.pipeline);
-            for chunk in self.chunks.iter() {
-                shadow_pass.set_vertex_buffer(1, chunk.instance_buffer.slice(..));
-                for mesh in &chunk.meshes {
-                    shadow_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
-                    shadow_pass
-                        .set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
-                    shadow_pass.set_bind_group(0, &self.light.bind_group, &[]);
-                    shadow_pass.draw_indexed(0..mesh.num_elements, 0, 0..1);
-                }
-            }
+            shadow_pass.set_bind_group(0, &self.light.bind_group, &[]);
+            self.chunk_buffers.bind(&mut shadow_pass);
+            self.chunk_buffers.draw(&mut shadow_pass, &batch);
         }
         {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
@@ -328,20 +349,14 @@ impl State {
             // / RENDER CHUNK
             // /
             render_pass.set_pipeline(&self.render_pipeline);
-            for chunk in self.chunks.iter() {
-                render_pass.set_vertex_buffer(1, chunk.instance_buffer.slice(..));
-                render_pass.set_bind_group(3, &self.shadow.bind_group, &[]);
-                for mesh in &chunk.meshes {
-                    let material = &self.materials[mesh.material];
-
-                    render_pass.draw_mesh(
-                        &mesh,
-                        &material,
-                        &self.camera.bind_group,
-                        &self.light.bind_group,
-                    );
-                }
-            }
+            // block textures are layers of the same texture arrays,
+            // so every chunk is drawn with a single call and the same bind groups
+            render_pass.set_bind_group(0, &self.block_material.bind_group, &[]);
+            render_pass.set_bind_group(1, &self.camera.bind_group, &[]);
+            render_pass.set_bind_group(2, &self.light.bind_group, &[]);
+            render_pass.set_bind_group(3, &self.shadow.bind_group, &[]);
+            self.chunk_buffers.bind(&mut render_pass);
+            self.chunk_buffers.draw(&mut render_pass, &batch);
         }
 
         staging_belt.finish();
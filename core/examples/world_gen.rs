@@ -1,8 +1,12 @@
-use core::{block::BlockKind, world::World};
-use std::fs;
+use core::{
+    block::BlockKind,
+    chunk::Chunk,
+    coords::{ChunkPos, LocalPos},
+    world::World,
+};
 pub fn main() {
     let start = instant::Instant::now();
-    let world = World::generate(13);
+    let mut world = World::generate(13);
 
     let duration = instant::Instant::now() - start;
     println!(
@@ -11,17 +15,42 @@ pub fn main() {
         duration.as_secs_f32()
     );
     println!(
-        "Size of world is {:#?}",
-        std::mem::size_of::<BlockKind>() * world.chunks().len()
+        "Size of world is {:#?}, it was {:#?} without palettes",
+        world
+            .chunks()
+            .values()
+            .map(|c| c.memory_size())
+            .sum::<usize>(),
+        std::mem::size_of::<BlockKind>() * world.chunks().len() * World::CHUNK_VOLUME
     );
-    println!("START SAVE TO FILE");
+    let terrain = Chunk::generate(1982, ChunkPos::new(0, 0));
+    println!(
+        "Terrain chunk takes {:?} bytes with {:?} bit indices, {:?} bytes as [usize]",
+        terrain.memory_size(),
+        terrain.bits(),
+        std::mem::size_of::<usize>() * World::CHUNK_VOLUME
+    );
+    println!("START SAVE TO DIRECTORY");
     let start = instant::Instant::now();
-    let file = fs::OpenOptions::new()
-        .write(true)
-        .create(true)
-        .open("world.json")
-        .unwrap();
-    serde_json::to_writer(file, &world).unwrap();
+    world.save("world").unwrap();
     let duration = instant::Instant::now() - start;
     println!("DONE: {:?}", duration.as_secs_f32());
+
+    println!("START LOAD FROM DIRECTORY");
+    let start = instant::Instant::now();
+    let mut loaded = World::load("world").unwrap();
+    let duration = instant::Instant::now() - start;
+    println!("DONE: {:?}", duration.as_secs_f32());
+    assert_eq!(world.seed(), loaded.seed());
+    assert!(world.chunks() == loaded.chunks());
+
+    // rewrite a single chunk and read it back without touching the others
+    let chunk = loaded.chunk_mut(ChunkPos::new(3, 4)).unwrap();
+    chunk.set_local(LocalPos::new(0, 0, 0), BlockKind::AIR);
+    let chunk = chunk.clone();
+    loaded.save_chunk(ChunkPos::new(3, 4)).unwrap();
+    let mut reopened = World::open("world").unwrap();
+    assert!(reopened.load_chunk(ChunkPos::new(3, 4)).unwrap());
+    assert!(reopened.chunk(ChunkPos::new(3, 4)).unwrap() == &chunk);
+    assert!(!reopened.load_chunk(ChunkPos::new(-40, 12)).unwrap());
 }
@@ -1,27 +1,52 @@
-use num_derive::{FromPrimitive, ToPrimitive};
+pub mod registry;
+
 use serde::{Deserialize, Serialize};
-use serde_repr::*;
-#[derive(Debug, Default, FromPrimitive, ToPrimitive, Serialize_repr, Deserialize_repr)]
-#[repr(u16)]
-pub enum BlockKind {
-    #[default]
-    Air = 0,
-    Stone,
-    Granite,
-    Diorite,
-    Andesite,
-    Grass,
-    Dirt,
-    //
-    COUNT,
-}
+
+pub use registry::{registry, Behaviour, BlockDef, BlockRegistry, RenderType};
+
+/// Id of a block in the [`BlockRegistry`]
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct BlockKind(pub u16);
+
 impl BlockKind {
-    pub fn len() -> usize {
-        Self::COUNT as usize
+    /// The only block every registry has to define
+    pub const AIR: BlockKind = BlockKind(0);
+
+    /// A block of the default registry by name, panics if there is no such block
+    pub fn named(name: &str) -> Self {
+        registry()
+            .kind(name)
+            .unwrap_or_else(|| panic!("unknown block {:?}", name))
+    }
+
+    pub fn def(&self) -> Option<&'static BlockDef> {
+        registry().get(*self)
+    }
+
+    pub fn is_opaque(&self) -> bool {
+        registry().is_opaque(*self)
+    }
+
+    pub fn is_solid(&self) -> bool {
+        registry().is_solid(*self)
+    }
+
+    pub fn is_liquid(&self) -> bool {
+        registry().is_liquid(*self)
+    }
+
+    pub fn hardness(&self) -> f32 {
+        registry().hardness(*self)
+    }
+
+    pub fn behaviour(&self) -> Behaviour {
+        registry().behaviour(*self)
     }
 }
 
-impl BlockKind {}
 #[derive(Debug, Default, Deserialize, Serialize)]
 pub struct Block {
     kind: BlockKind,
@@ -0,0 +1,306 @@
+//! Blocks are defined in `res/blocks.json` rather than in code:
+//!
+//! ```json
+//! { "id": 5, "name": "grass", "textures": { "top": "grass_top", "side": "dirt", "bottom": "dirt" }, "hardness": 0.6 }
+//! { "id": 12, "name": "ice", "textures": { "all": "ice" }, "opaque": false, "render": "translucent" }
+//! { "id": 13, "name": "water", "textures": { "all": "water" }, "opaque": false, "solid": false, "liquid": true, "render": "water" }
+//! { "id": 15, "name": "sand", "textures": { "all": "sand" }, "behaviour": "falling" }
+//! ```
+//!
+//! The file is read when the registry is first used, so blocks can be added
+//! without recompiling, see [`registry_path`].
+//!
+//! A texture name `n` refers to `n.png`, `n_n.png` and `n_s.png` (diffuse, normal and specular)
+//! in `res/textures/blocks`. Every distinct texture becomes a layer of the block texture arrays.
+use anyhow::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::BlockKind;
+
+/// Textures of the block faces, a specific face wins over `side` and `side` wins over `all`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FaceTextures {
+    pub all: Option<String>,
+    pub side: Option<String>,
+    pub top: Option<String>,
+    pub bottom: Option<String>,
+    pub back: Option<String>,
+    pub front: Option<String>,
+    pub left: Option<String>,
+    pub right: Option<String>,
+}
+
+impl FaceTextures {
+    /// Texture names in [`crate::voxel::Voxel::FACES`] order
+    fn resolve(&self) -> Option<[&str; 6]> {
+        let side = self.side.as_ref().or(self.all.as_ref());
+        let faces = [
+            self.back.as_ref().or(side),
+            self.front.as_ref().or(side),
+            self.top.as_ref().or(self.all.as_ref()),
+            self.bottom.as_ref().or(self.all.as_ref()),
+            self.left.as_ref().or(side),
+            self.right.as_ref().or(side),
+        ];
+        let mut out = [""; 6];
+        for (name, face) in out.iter_mut().zip(faces) {
+            *name = face?.as_str();
+        }
+        Some(out)
+    }
+}
+
+/// How the faces of a block are drawn, every type has its own pipeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RenderType {
+    /// Texture alpha is ignored
+    #[default]
+    Opaque,
+    /// Texels with a low alpha are discarded, glass or leaves for example
+    Cutout,
+    /// Blended with what is behind, drawn after everything else back to front
+    Translucent,
+    /// Animated surface reflecting the sky, drawn by the water pipeline after the translucent blocks
+    Water,
+}
+
+impl RenderType {
+    pub const COUNT: usize = 4;
+    /// In drawing order
+    pub const ALL: [RenderType; Self::COUNT] = [
+        RenderType::Opaque,
+        RenderType::Cutout,
+        RenderType::Translucent,
+        RenderType::Water,
+    ];
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// What a block does on the ticks of the world, see [`crate::world::tick`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Behaviour {
+    /// Never changes by itself
+    #[default]
+    Static,
+    /// Spreads onto dirt next to it on random ticks, turns into dirt when covered
+    Grass,
+    /// Falls down when there is nothing solid below it
+    Falling,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BlockDef {
+    pub id: u16,
+    pub name: String,
+    /// A block without textures is never drawn
+    #[serde(default)]
+    pub textures: Option<FaceTextures>,
+    /// An opaque block hides the faces of its neighbours
+    #[serde(default = "yes")]
+    pub opaque: bool,
+    /// A solid block can not be walked through
+    #[serde(default = "yes")]
+    pub solid: bool,
+    /// Seconds to break the block, a negative hardness can not be broken
+    #[serde(default = "one")]
+    pub hardness: f32,
+    /// Light emitted by the block, 0 to 15
+    #[serde(default)]
+    pub light: u8,
+    /// A liquid has a lowered top face unless the same liquid is above it
+    #[serde(default)]
+    pub liquid: bool,
+    #[serde(default)]
+    pub render: RenderType,
+    #[serde(default)]
+    pub behaviour: Behaviour,
+}
+
+fn yes() -> bool {
+    true
+}
+
+fn one() -> f32 {
+    1.0
+}
+
+#[derive(Debug, Default)]
+pub struct BlockRegistry {
+    /// Definitions indexed by block id
+    blocks: Vec<Option<BlockDef>>,
+    names: HashMap<String, BlockKind>,
+    /// Texture array layer of every face of a block, indexed by block id
+    layers: Vec<[u16; 6]>,
+    /// Texture names in texture array layer order
+    textures: Vec<String>,
+}
+
+impl BlockRegistry {
+    pub fn from_json(json: &str) -> Result<Self> {
+        let defs: Vec<BlockDef> = serde_json::from_str(json)?;
+        Self::new(defs)
+    }
+
+    pub fn load<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let json = std::fs::read_to_string(path.as_ref())
+            .with_context(|| format!("can not read block registry {:?}", path.as_ref()))?;
+        Self::from_json(&json)
+    }
+
+    pub fn new(defs: Vec<BlockDef>) -> Result<Self> {
+        let mut registry = BlockRegistry::default();
+        let len = defs
+            .iter()
+            .map(|def| def.id as usize + 1)
+            .max()
+            .unwrap_or(0);
+        registry.blocks.resize(len, None);
+        registry.layers.resize(len, [0; 6]);
+        for def in defs {
+            let kind = BlockKind(def.id);
+            ensure!(
+                registry.blocks[def.id as usize].is_none(),
+                "block id {} is defined twice",
+                def.id
+            );
+            ensure!(
+                registry.names.insert(def.name.clone(), kind).is_none(),
+                "block name {:?} is defined twice",
+                def.name
+            );
+            if let Some(textures) = &def.textures {
+                let faces = textures
+                    .resolve()
+                    .with_context(|| format!("block {:?} misses a face texture", def.name))?;
+                for (face_id, name) in faces.iter().enumerate() {
+                    let layer = match registry.textures.iter().position(|t| t == name) {
+                        Some(layer) => layer,
+                        None => {
+                            registry.textures.push(name.to_string());
+                            registry.textures.len() - 1
+                        }
+                    };
+                    registry.layers[def.id as usize][face_id] = layer as u16;
+                }
+            }
+            ensure!(
+                def.render == RenderType::Opaque || !def.opaque,
+                "block {:?} is {:?} and can not be opaque",
+                def.name,
+                def.render
+            );
+            let id = def.id as usize;
+            registry.blocks[id] = Some(def);
+        }
+        let air = registry
+            .get(BlockKind::AIR)
+            .context("block id 0 has to be air")?;
+        ensure!(
+            air.textures.is_none() && !air.opaque && !air.solid,
+            "block id 0 has to be invisible, transparent and not solid"
+        );
+        Ok(registry)
+    }
+
+    /// Number of block ids, including the ones without a definition
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    pub fn get(&self, kind: BlockKind) -> Option<&BlockDef> {
+        self.blocks.get(kind.0 as usize)?.as_ref()
+    }
+
+    pub fn kind(&self, name: &str) -> Option<BlockKind> {
+        self.names.get(name).copied()
+    }
+
+    /// Every defined block
+    pub fn blocks(&self) -> impl Iterator<Item = &BlockDef> {
+        self.blocks.iter().flatten()
+    }
+
+    pub fn is_visible(&self, kind: BlockKind) -> bool {
+        self.get(kind).is_some_and(|def| def.textures.is_some())
+    }
+
+    pub fn is_opaque(&self, kind: BlockKind) -> bool {
+        self.get(kind).is_some_and(|def| def.opaque)
+    }
+
+    pub fn is_solid(&self, kind: BlockKind) -> bool {
+        self.get(kind).is_some_and(|def| def.solid)
+    }
+
+    pub fn is_liquid(&self, kind: BlockKind) -> bool {
+        self.get(kind).is_some_and(|def| def.liquid)
+    }
+
+    pub fn render_type(&self, kind: BlockKind) -> RenderType {
+        self.get(kind).map_or(RenderType::Opaque, |def| def.render)
+    }
+
+    pub fn behaviour(&self, kind: BlockKind) -> Behaviour {
+        self.get(kind)
+            .map_or(Behaviour::Static, |def| def.behaviour)
+    }
+
+    pub fn hardness(&self, kind: BlockKind) -> f32 {
+        self.get(kind).map_or(-1.0, |def| def.hardness)
+    }
+
+    pub fn light(&self, kind: BlockKind) -> u8 {
+        self.get(kind).map_or(0, |def| def.light)
+    }
+
+    /// Texture array layer of a block face in [`crate::voxel::Voxel::FACES`] order
+    pub fn face_layer(&self, kind: BlockKind, face_id: usize) -> usize {
+        self.layers[kind.0 as usize][face_id] as usize
+    }
+
+    /// Texture names in texture array layer order
+    pub fn textures(&self) -> &[String] {
+        &self.textures
+    }
+}
+
+/// Environment variable pointing to a block registry used instead of `res/blocks.json`
+pub const REGISTRY_VAR: &str = "ALVOX_BLOCKS";
+
+/// Path the block registry is read from on first use, see [`REGISTRY_VAR`]
+pub fn registry_path() -> PathBuf {
+    match std::env::var_os(REGISTRY_VAR) {
+        Some(path) => PathBuf::from(path),
+        None => Path::new(env!("OUT_DIR")).join("res").join("blocks.json"),
+    }
+}
+
+lazy_static! {
+    static ref REGISTRY: BlockRegistry = {
+        let path = registry_path();
+        BlockRegistry::load(&path)
+            .unwrap_or_else(|e| panic!("{:?} is not a valid block registry: {:?}", path, e))
+    };
+}
+
+/// The registry read from [`registry_path`] at runtime,
+/// used by the mesher, the block textures and the world generator
+pub fn registry() -> &'static BlockRegistry {
+    &REGISTRY
+}
@@ -0,0 +1,180 @@
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
+
+/// Id of an allocation, it stays the same when [`RangeAllocator::compact`] moves the allocation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AllocationId(u64);
+
+/// Where [`RangeAllocator::compact`] put an allocation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Move {
+    pub id: AllocationId,
+    pub from: u64,
+    pub to: u64,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AllocatorStats {
+    pub capacity: u64,
+    pub used: u64,
+    pub allocations: usize,
+    /// Free ranges between and after the allocations
+    pub free_ranges: usize,
+    pub largest_free: u64,
+}
+
+impl AllocatorStats {
+    pub fn free(&self) -> u64 {
+        self.capacity - self.used
+    }
+
+    /// Part of the free space outside of the largest free range, from 0 to 1
+    pub fn fragmentation(&self) -> f32 {
+        if self.free() == 0 {
+            0.0
+        } else {
+            1.0 - self.largest_free as f32 / self.free() as f32
+        }
+    }
+}
+
+/// Hands out ranges of a large buffer, it only does the bookkeeping
+/// and knows nothing about the GPU.
+///
+/// Ranges are taken first fit from a list of free ranges, freed ranges are merged
+/// with the free ranges next to them. When no free range is large enough,
+/// the allocations can be packed at the start of the buffer by [`RangeAllocator::compact`]
+/// and the buffer can grow by [`RangeAllocator::grow`], the owner of the buffer moves the data.
+#[derive(Debug, Clone)]
+pub struct RangeAllocator {
+    capacity: u64,
+    used: u64,
+    /// Size of every free range by its offset, free ranges never touch each other
+    free: BTreeMap<u64, u64>,
+    allocations: HashMap<AllocationId, Range<u64>>,
+    next_id: u64,
+}
+
+impl RangeAllocator {
+    pub fn new(capacity: u64) -> Self {
+        let mut free = BTreeMap::new();
+        if capacity > 0 {
+            free.insert(0, capacity);
+        }
+        RangeAllocator {
+            capacity,
+            used: 0,
+            free,
+            allocations: HashMap::new(),
+            next_id: 0,
+        }
+    }
+
+    pub fn capacity(&self) -> u64 {
+        self.capacity
+    }
+
+    /// Takes `size` units, `None` if no free range is large enough
+    pub fn alloc(&mut self, size: u64) -> Option<AllocationId> {
+        let range = if size == 0 {
+            0..0
+        } else {
+            let (offset, free) = self
+                .free
+                .iter()
+                .map(|(offset, free)| (*offset, *free))
+                .find(|(_, free)| *free >= size)?;
+            self.free.remove(&offset);
+            if free > size {
+                self.free.insert(offset + size, free - size);
+            }
+            offset..offset + size
+        };
+        let id = AllocationId(self.next_id);
+        self.next_id += 1;
+        self.used += size;
+        self.allocations.insert(id, range);
+        Some(id)
+    }
+
+    /// Frees an allocation and returns where it was, `None` if it was freed already
+    pub fn free(&mut self, id: AllocationId) -> Option<Range<u64>> {
+        let range = self.allocations.remove(&id)?;
+        self.used -= range.end - range.start;
+        self.release(range.clone());
+        Some(range)
+    }
+
+    pub fn get(&self, id: AllocationId) -> Option<Range<u64>> {
+        self.allocations.get(&id).cloned()
+    }
+
+    /// Adds space at the end, a smaller capacity is ignored
+    pub fn grow(&mut self, capacity: u64) {
+        if capacity > self.capacity {
+            self.release(self.capacity..capacity);
+            self.capacity = capacity;
+        }
+    }
+
+    /// Packs every allocation at the start in offset order, the free space becomes
+    /// a single range at the end. Returns where every allocation was and where it is now
+    pub fn compact(&mut self) -> Vec<Move> {
+        let mut allocations: Vec<_> = self
+            .allocations
+            .iter_mut()
+            .map(|(id, range)| (*id, range))
+            .collect();
+        allocations.sort_by_key(|(_, range)| range.start);
+        let mut offset = 0;
+        let moves = allocations
+            .into_iter()
+            .map(|(id, range)| {
+                let size = range.end - range.start;
+                let from = range.start;
+                *range = offset..offset + size;
+                offset += size;
+                Move {
+                    id,
+                    from,
+                    to: range.start,
+                    size,
+                }
+            })
+            .collect();
+        self.free.clear();
+        if offset < self.capacity {
+            self.free.insert(offset, self.capacity - offset);
+        }
+        moves
+    }
+
+    pub fn stats(&self) -> AllocatorStats {
+        AllocatorStats {
+            capacity: self.capacity,
+            used: self.used,
+            allocations: self.allocations.len(),
+            free_ranges: self.free.len(),
+            largest_free: self.free.values().max().cloned().unwrap_or(0),
+        }
+    }
+
+    /// Marks a range as free, merging it with the free ranges before and after it
+    fn release(&mut self, range: Range<u64>) {
+        if range.is_empty() {
+            return;
+        }
+        let (mut start, mut end) = (range.start, range.end);
+        if let Some((&before, &size)) = self.free.range(..start).next_back() {
+            if before + size == start {
+                self.free.remove(&before);
+                start = before;
+            }
+        }
+        if let Some(size) = self.free.remove(&end) {
+            end += size;
+        }
+        self.free.insert(start, end - start);
+    }
+}
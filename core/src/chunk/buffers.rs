@@ -0,0 +1,444 @@
+use std::iter;
+use std::mem;
+
+use crate::block::RenderType;
+use crate::instance::InstanceRaw;
+use crate::vertex::ChunkVertex;
+
+use super::allocator::{AllocationId, AllocatorStats, RangeAllocator};
+
+/// Arguments of an indexed draw, laid out as [`wgpu::util::DrawIndexedIndirect`]
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct DrawIndexed {
+    pub index_count: u32,
+    pub instance_count: u32,
+    pub first_index: u32,
+    pub base_vertex: i32,
+    pub first_instance: u32,
+}
+
+/// Where the geometry and the instance of a chunk mesh are in the [`ChunkBuffers`],
+/// the geometry may move when the buffers are packed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MeshSlice {
+    pub vertices: AllocationId,
+    /// Indices of every [`RenderType`] one after another
+    pub indices: AllocationId,
+    pub index_counts: [u32; RenderType::COUNT],
+    pub instance: u32,
+}
+
+/// How the chunk draws of a pass are issued, it depends on the device features
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrawMode {
+    /// A single `multi_draw_indexed_indirect` per pass
+    MultiDrawIndirect,
+    /// A `draw_indexed_indirect` per chunk
+    DrawIndirect,
+    /// A `draw_indexed` per chunk, indirect draws can not pick the chunk instance
+    Direct,
+}
+
+impl DrawMode {
+    /// Features which enable the indirect modes when the adapter has them
+    pub const FEATURES: wgpu::Features =
+        wgpu::Features::MULTI_DRAW_INDIRECT.union(wgpu::Features::INDIRECT_FIRST_INSTANCE);
+
+    pub fn from_features(features: wgpu::Features) -> Self {
+        if !features.contains(wgpu::Features::INDIRECT_FIRST_INSTANCE) {
+            DrawMode::Direct
+        } else if features.contains(wgpu::Features::MULTI_DRAW_INDIRECT) {
+            DrawMode::MultiDrawIndirect
+        } else {
+            DrawMode::DrawIndirect
+        }
+    }
+}
+
+/// Draw commands of one pass, written to the indirect buffer by [`ChunkBuffers::batches`]
+#[derive(Debug, Default)]
+pub struct DrawBatch {
+    /// Byte offset of the first command in the indirect buffer
+    offset: wgpu::BufferAddress,
+    commands: Vec<DrawIndexed>,
+}
+
+impl DrawBatch {
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+}
+
+/// A GPU buffer split into ranges by a [`RangeAllocator`]. When no free range is large
+/// enough, the data is packed into a new buffer, a larger one if the old one is mostly full
+#[derive(Debug)]
+struct SharedBuffer {
+    label: &'static str,
+    usage: wgpu::BufferUsages,
+    buffer: wgpu::Buffer,
+    /// Byte ranges of the buffer
+    allocator: RangeAllocator,
+}
+
+impl SharedBuffer {
+    /// Packing grows the buffer until it is at most this full, in percent
+    const MAX_LOAD: u64 = 75;
+
+    fn new(
+        device: &wgpu::Device,
+        label: &'static str,
+        usage: wgpu::BufferUsages,
+        capacity: wgpu::BufferAddress,
+    ) -> Self {
+        let usage = usage | wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST;
+        SharedBuffer {
+            label,
+            usage,
+            buffer: Self::create(device, label, usage, capacity),
+            allocator: RangeAllocator::new(capacity),
+        }
+    }
+
+    fn create(
+        device: &wgpu::Device,
+        label: &'static str,
+        usage: wgpu::BufferUsages,
+        size: wgpu::BufferAddress,
+    ) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size,
+            usage,
+            mapped_at_creation: false,
+        })
+    }
+
+    /// Stores data in a free range, `None` if it does not fit within the device limits
+    fn insert(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        data: &[u8],
+    ) -> Option<AllocationId> {
+        let size = data.len() as wgpu::BufferAddress;
+        let id = match self.allocator.alloc(size) {
+            Some(id) => id,
+            None => {
+                self.repack(device, queue, size)?;
+                self.allocator.alloc(size)?
+            }
+        };
+        if size > 0 {
+            queue.write_buffer(&self.buffer, self.offset(id), data);
+        }
+        Some(id)
+    }
+
+    fn remove(&mut self, id: AllocationId) {
+        self.allocator.free(id);
+    }
+
+    /// Byte offset of an allocation
+    fn offset(&self, id: AllocationId) -> wgpu::BufferAddress {
+        self.allocator.get(id).map_or(0, |range| range.start)
+    }
+
+    /// Packs the data at the start of a new buffer with room for `size` more bytes.
+    /// The capacity doubles while the buffer would be more than [`Self::MAX_LOAD`] full
+    fn repack(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        size: wgpu::BufferAddress,
+    ) -> Option<()> {
+        let max = device.limits().max_buffer_size;
+        let stats = self.allocator.stats();
+        let needed = stats.used + size;
+        if needed > max {
+            log::error!("{} can not hold {} bytes", self.label, needed);
+            return None;
+        }
+        let mut capacity = stats.capacity;
+        while needed * 100 > capacity * Self::MAX_LOAD && capacity < max {
+            capacity *= 2;
+        }
+        let capacity = capacity.min(max);
+        let moves = self.allocator.compact();
+        self.allocator.grow(capacity);
+        let buffer = Self::create(device, self.label, self.usage, capacity);
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Chunk Buffer Repack Encoder"),
+        });
+        for m in moves.iter().filter(|m| m.size > 0) {
+            encoder.copy_buffer_to_buffer(&self.buffer, m.from, &buffer, m.to, m.size);
+        }
+        queue.submit(iter::once(encoder.finish()));
+        log::debug!(
+            "{} is packed into {} bytes, {} of them used by {} allocations",
+            self.label,
+            capacity,
+            stats.used,
+            moves.len()
+        );
+        self.buffer = buffer;
+        Some(())
+    }
+}
+
+/// Usage of the [`ChunkBuffers`], in bytes for the geometry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BufferStats {
+    pub vertices: AllocatorStats,
+    pub indices: AllocatorStats,
+    pub instances: usize,
+}
+
+/// Geometry of every chunk in a few large buffers shared by all the chunks.
+///
+/// The vertices and indices of a mesh take ranges of the shared vertex and index buffers,
+/// its model matrix takes a slot of the shared instance buffer. A pass binds the buffers
+/// once and draws all of its chunks from an indirect buffer, with a single call when
+/// the device supports multi draw, see [`DrawMode`].
+///
+/// Space of removed meshes is reused, the buffers are packed when it is too fragmented.
+#[derive(Debug)]
+pub struct ChunkBuffers {
+    mode: DrawMode,
+    vertices: SharedBuffer,
+    indices: SharedBuffer,
+    instances: wgpu::Buffer,
+    /// Instances the instance buffer has room for
+    instance_capacity: usize,
+    /// Copy of the instance buffer, it is uploaded again when the buffer grows
+    instance_data: Vec<InstanceRaw>,
+    free_instances: Vec<u32>,
+    indirect: wgpu::Buffer,
+    /// Commands the indirect buffer has room for
+    indirect_capacity: usize,
+}
+
+impl ChunkBuffers {
+    const VERTEX_CAPACITY: wgpu::BufferAddress = 16 << 20;
+    const INDEX_CAPACITY: wgpu::BufferAddress = 16 << 20;
+    const INSTANCE_CAPACITY: usize = 1024;
+    const INDIRECT_CAPACITY: usize = 1024;
+
+    pub fn new(device: &wgpu::Device) -> Self {
+        let mode = DrawMode::from_features(device.features());
+        log::info!("chunks are drawn with {:?}", mode);
+        ChunkBuffers {
+            mode,
+            vertices: SharedBuffer::new(
+                device,
+                "Chunk Vertex Buffer",
+                wgpu::BufferUsages::VERTEX,
+                Self::VERTEX_CAPACITY,
+            ),
+            indices: SharedBuffer::new(
+                device,
+                "Chunk Index Buffer",
+                wgpu::BufferUsages::INDEX,
+                Self::INDEX_CAPACITY,
+            ),
+            instances: Self::instance_buffer(device, Self::INSTANCE_CAPACITY),
+            instance_capacity: Self::INSTANCE_CAPACITY,
+            instance_data: Vec::new(),
+            free_instances: Vec::new(),
+            indirect: Self::indirect_buffer(device, Self::INDIRECT_CAPACITY),
+            indirect_capacity: Self::INDIRECT_CAPACITY,
+        }
+    }
+
+    fn instance_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Chunk Instance Buffer"),
+            size: (capacity * mem::size_of::<InstanceRaw>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn indirect_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Chunk Indirect Buffer"),
+            size: (capacity * mem::size_of::<DrawIndexed>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::INDIRECT | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    pub fn mode(&self) -> DrawMode {
+        self.mode
+    }
+
+    /// Uploads a mesh and its instance, `None` if the buffers are full
+    pub fn insert(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        vertices: &[ChunkVertex],
+        indices: &[Vec<u32>; RenderType::COUNT],
+        instance: InstanceRaw,
+    ) -> Option<MeshSlice> {
+        let vertices = self
+            .vertices
+            .insert(device, queue, bytemuck::cast_slice(vertices))?;
+        let index_counts = RenderType::ALL.map(|render| indices[render.index()].len() as u32);
+        let indices =
+            match self
+                .indices
+                .insert(device, queue, bytemuck::cast_slice(&indices.concat()))
+            {
+                Some(id) => id,
+                None => {
+                    self.vertices.remove(vertices);
+                    return None;
+                }
+            };
+        Some(MeshSlice {
+            vertices,
+            indices,
+            index_counts,
+            instance: self.insert_instance(device, queue, instance),
+        })
+    }
+
+    fn insert_instance(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        instance: InstanceRaw,
+    ) -> u32 {
+        let size = mem::size_of::<InstanceRaw>() as wgpu::BufferAddress;
+        if let Some(slot) = self.free_instances.pop() {
+            self.instance_data[slot as usize] = instance;
+            queue.write_buffer(
+                &self.instances,
+                slot as wgpu::BufferAddress * size,
+                bytemuck::cast_slice(&[instance]),
+            );
+            return slot;
+        }
+        let slot = self.instance_data.len();
+        self.instance_data.push(instance);
+        if self.instance_data.len() > self.instance_capacity {
+            self.instance_capacity *= 2;
+            self.instances = Self::instance_buffer(device, self.instance_capacity);
+            queue.write_buffer(
+                &self.instances,
+                0,
+                bytemuck::cast_slice(&self.instance_data),
+            );
+        } else {
+            queue.write_buffer(
+                &self.instances,
+                slot as wgpu::BufferAddress * size,
+                bytemuck::cast_slice(&[instance]),
+            );
+        }
+        slot as u32
+    }
+
+    /// Frees the geometry and the instance of a mesh
+    pub fn remove(&mut self, mesh: &MeshSlice) {
+        self.vertices.remove(mesh.vertices);
+        self.indices.remove(mesh.indices);
+        self.free_instances.push(mesh.instance);
+    }
+
+    /// Where the faces of a render type of a mesh are drawn from at the moment,
+    /// `None` if the mesh has no such faces
+    pub fn command(&self, mesh: &MeshSlice, render: RenderType) -> Option<DrawIndexed> {
+        let index_count = mesh.index_counts[render.index()];
+        if index_count == 0 {
+            return None;
+        }
+        let vertex_size = mem::size_of::<ChunkVertex>() as wgpu::BufferAddress;
+        let index_size = mem::size_of::<u32>() as wgpu::BufferAddress;
+        let before: u32 = mesh.index_counts[..render.index()].iter().sum();
+        Some(DrawIndexed {
+            index_count,
+            instance_count: 1,
+            first_index: (self.indices.offset(mesh.indices) / index_size) as u32 + before,
+            base_vertex: (self.vertices.offset(mesh.vertices) / vertex_size) as i32,
+            first_instance: mesh.instance,
+        })
+    }
+
+    pub fn stats(&self) -> BufferStats {
+        BufferStats {
+            vertices: self.vertices.allocator.stats(),
+            indices: self.indices.allocator.stats(),
+            instances: self.instance_data.len() - self.free_instances.len(),
+        }
+    }
+
+    /// Writes the draws of several passes to the indirect buffer, a batch per pass
+    pub fn batches<const N: usize>(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        draws: [Vec<DrawIndexed>; N],
+    ) -> [DrawBatch; N] {
+        let total = draws.iter().map(Vec::len).sum::<usize>();
+        if self.mode != DrawMode::Direct && total > self.indirect_capacity {
+            self.indirect_capacity = total.next_power_of_two();
+            self.indirect = Self::indirect_buffer(device, self.indirect_capacity);
+        }
+        let mut offset = 0;
+        draws.map(|commands| {
+            let batch = DrawBatch { offset, commands };
+            if self.mode != DrawMode::Direct && !batch.is_empty() {
+                queue.write_buffer(
+                    &self.indirect,
+                    offset,
+                    bytemuck::cast_slice(&batch.commands),
+                );
+            }
+            offset += (batch.len() * mem::size_of::<DrawIndexed>()) as wgpu::BufferAddress;
+            batch
+        })
+    }
+
+    /// Binds the shared buffers to a pass using [`ChunkVertex`] and [`InstanceRaw`] at slots 0 and 1
+    pub fn bind<'a>(&'a self, pass: &mut wgpu::RenderPass<'a>) {
+        pass.set_vertex_buffer(0, self.vertices.buffer.slice(..));
+        pass.set_vertex_buffer(1, self.instances.slice(..));
+        pass.set_index_buffer(self.indices.buffer.slice(..), wgpu::IndexFormat::Uint32);
+    }
+
+    /// Draws a batch with the buffers bound by [`ChunkBuffers::bind`]
+    pub fn draw<'a>(&'a self, pass: &mut wgpu::RenderPass<'a>, batch: &DrawBatch) {
+        let stride = mem::size_of::<DrawIndexed>() as wgpu::BufferAddress;
+        match self.mode {
+            DrawMode::MultiDrawIndirect => {
+                if !batch.is_empty() {
+                    pass.multi_draw_indexed_indirect(
+                        &self.indirect,
+                        batch.offset,
+                        batch.len() as u32,
+                    );
+                }
+            }
+            DrawMode::DrawIndirect => {
+                for i in 0..batch.len() as wgpu::BufferAddress {
+                    pass.draw_indexed_indirect(&self.indirect, batch.offset + i * stride);
+                }
+            }
+            DrawMode::Direct => {
+                for draw in &batch.commands {
+                    pass.draw_indexed(
+                        draw.first_index..draw.first_index + draw.index_count,
+                        draw.base_vertex,
+                        draw.first_instance..draw.first_instance + draw.instance_count,
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+
+use crate::block::{registry, BlockKind};
+use crate::coords::LocalPos;
+
+use super::storage::ChunkStorage;
+use super::Chunk;
+
+/// Level of detail of a chunk mesh, a voxel of the mesh is `2^lod` blocks wide
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Lod(pub u8);
+
+impl Lod {
+    /// The full mesh
+    pub const FULL: Lod = Lod(0);
+    /// 8x8x8 blocks per voxel
+    pub const MAX: Lod = Lod(3);
+    pub const COUNT: usize = Self::MAX.0 as usize + 1;
+
+    /// Blocks per voxel along each axis
+    pub fn scale(self) -> usize {
+        1 << self.0
+    }
+
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+
+    /// Every level from the full mesh to the coarsest one
+    pub fn all() -> impl Iterator<Item = Lod> {
+        (0..=Self::MAX.0).map(Lod)
+    }
+
+    /// Level of a chunk `distance` chunks away from the camera. Chunks closer than
+    /// `full_distance` get the full mesh, the level goes up every time the distance doubles
+    pub fn for_distance(distance: i32, full_distance: i32) -> Lod {
+        let mut lod = Lod::FULL;
+        let mut limit = full_distance.max(1);
+        while distance >= limit && lod < Self::MAX {
+            lod.0 += 1;
+            limit *= 2;
+        }
+        lod
+    }
+}
+
+/// Blocks of a chunk merged into voxels of `scale³` blocks.
+///
+/// A voxel is filled when any of its blocks is visible, so the surface of a coarse level
+/// never lies below the surface of a finer one and no hole opens where they meet.
+/// It takes the most common visible block of its top-most layer with any,
+/// grass stays on top of dirt for example
+#[derive(Debug, Clone)]
+pub struct Downsampled {
+    /// Voxels along each axis
+    pub size: [usize; 3],
+    voxels: Vec<BlockKind>,
+}
+
+impl Downsampled {
+    pub fn new(blocks: &ChunkStorage, lod: Lod) -> Self {
+        let scale = lod.scale();
+        let size = [
+            Chunk::WIDTH / scale,
+            Chunk::HEIGHT / scale,
+            Chunk::WIDTH / scale,
+        ];
+        let mut voxels = Vec::with_capacity(size[0] * size[1] * size[2]);
+        for y in 0..size[1] {
+            for z in 0..size[2] {
+                for x in 0..size[0] {
+                    voxels.push(Self::merge(blocks, [x, y, z].map(|c| c * scale), scale));
+                }
+            }
+        }
+        Downsampled { size, voxels }
+    }
+
+    fn merge(blocks: &ChunkStorage, [x0, y0, z0]: [usize; 3], scale: usize) -> BlockKind {
+        let registry = registry();
+        let mut counts = HashMap::new();
+        for y in (y0..y0 + scale).rev() {
+            for z in z0..z0 + scale {
+                for x in x0..x0 + scale {
+                    let kind = blocks.get_local(LocalPos::new(x, y, z));
+                    if registry.is_visible(kind) {
+                        *counts.entry(kind).or_insert(0) += 1;
+                    }
+                }
+            }
+            if let Some((kind, _)) = counts.iter().max_by_key(|(kind, count)| (**count, kind.0)) {
+                return *kind;
+            }
+        }
+        BlockKind::AIR
+    }
+
+    /// `None` outside of the chunk
+    pub fn get(&self, [x, y, z]: [i32; 3]) -> Option<BlockKind> {
+        let [sx, sy, sz] = self.size.map(|s| s as i32);
+        if x < 0 || y < 0 || z < 0 || x >= sx || y >= sy || z >= sz {
+            return None;
+        }
+        Some(self.voxels[((y * sz + z) * sx + x) as usize])
+    }
+}
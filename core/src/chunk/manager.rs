@@ -0,0 +1,586 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use cgmath::EuclideanSpace;
+
+use crate::camera::Camera;
+use crate::coords::{BlockPos, ChunkPos};
+use crate::world::World;
+
+use super::buffers::ChunkBuffers;
+use super::lod::Lod;
+use super::mesher::Mesher;
+use super::queue::{LoadQueue, Viewer, Work};
+use super::visibility;
+use super::worker::{Job, JobHandle, JobKind, JobOutput, JobResult, NeighbourBlocks, WorkerPool};
+use super::Chunk;
+
+/// Keeps the blocks of the chunks within `radius` from `center` loaded
+/// wherever the camera is, for the spawn area or a ticking machine for example.
+/// Pinned chunks are not drawn unless they are within the render distance
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoadTicket {
+    pub center: ChunkPos,
+    pub radius: i32,
+}
+
+impl LoadTicket {
+    pub fn new(center: ChunkPos, radius: i32) -> Self {
+        LoadTicket { center, radius }
+    }
+
+    pub fn contains(&self, pos: ChunkPos) -> bool {
+        ChunkManager::distance(self.center, pos) <= self.radius
+    }
+}
+
+/// Id of a [`LoadTicket`] added to a [`ChunkManager`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TicketId(u64);
+
+/// Load tickets by id, a chunk stays pinned while any of the tickets contains it
+#[derive(Debug, Default)]
+pub struct Tickets {
+    tickets: HashMap<TicketId, LoadTicket>,
+    next: u64,
+}
+
+impl Tickets {
+    pub fn add(&mut self, ticket: LoadTicket) -> TicketId {
+        let id = TicketId(self.next);
+        self.next += 1;
+        self.tickets.insert(id, ticket);
+        id
+    }
+
+    pub fn remove(&mut self, id: TicketId) -> Option<LoadTicket> {
+        self.tickets.remove(&id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (TicketId, &LoadTicket)> {
+        self.tickets.iter().map(|(id, ticket)| (*id, ticket))
+    }
+
+    /// `true` if a ticket keeps the chunk loaded
+    pub fn pins(&self, pos: ChunkPos) -> bool {
+        self.tickets.values().any(|ticket| ticket.contains(pos))
+    }
+
+    /// Chunks of every ticket, a chunk of overlapping tickets comes once per ticket
+    pub fn chunks(&self) -> impl Iterator<Item = ChunkPos> + '_ {
+        self.tickets
+            .values()
+            .flat_map(|ticket| ChunkManager::around(ticket.center, ticket.radius))
+    }
+
+    pub fn len(&self) -> usize {
+        self.tickets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tickets.is_empty()
+    }
+}
+
+/// Streams chunks around the camera.
+///
+/// Chunks within `render_distance` chunks from the chunk of the camera are meshed
+/// at the level of detail of their distance, see [`Lod::for_distance`], and meshed again
+/// on the workers when the camera moves them to another level.
+/// Their blocks and the blocks of one more ring of chunks are kept in the [`World`],
+/// so every meshed chunk has all of its neighbours and its border faces are culled.
+/// Blocks are read from the world save when there is one and generated otherwise.
+///
+/// Chunks of [`LoadTicket`]s are loaded as well, whatever the camera does.
+///
+/// Generation and meshing wait in a [`LoadQueue`], the closest chunks in front of
+/// the camera first, and run on a [`WorkerPool`]. Finished meshes are uploaded
+/// to the [`ChunkBuffers`] on the render thread, at most `upload_budget` of them per frame.
+#[derive(Debug)]
+pub struct ChunkManager {
+    render_distance: i32,
+    /// Mesher used for new chunks
+    pub mesher: Mesher,
+    /// Meshes uploaded per frame at most
+    pub upload_budget: usize,
+    /// Chunks closer than this are drawn with the full mesh, `None` draws every chunk in full
+    lod_distance: Option<i32>,
+    /// Chunk of the camera at the last update, `None` forces the next update
+    center: Option<ChunkPos>,
+    chunks: HashMap<ChunkPos, Chunk>,
+    /// Shared GPU buffers with the meshes of all the chunks
+    pub buffers: ChunkBuffers,
+    workers: WorkerPool,
+    /// Work waiting for a free worker
+    queue: LoadQueue,
+    /// Jobs sent to the workers without a result yet
+    in_flight: usize,
+    /// Chunks queued for generation or being generated
+    generating: HashMap<ChunkPos, JobHandle>,
+    /// Chunks queued for meshing, being meshed or waiting for upload
+    meshing: HashMap<ChunkPos, JobHandle>,
+    /// Chunks with mesh work still in the queue, their blocks are copied once it is sent
+    unsent: HashSet<ChunkPos>,
+    /// Meshes waiting for upload
+    ready: VecDeque<JobResult>,
+    /// Set when blocks of chunks were loaded, some chunks may be ready for meshing
+    loaded: bool,
+    tickets: Tickets,
+}
+
+impl ChunkManager {
+    pub const MIN_RENDER_DISTANCE: i32 = 1;
+    pub const MAX_RENDER_DISTANCE: i32 = 32;
+    pub const UPLOAD_BUDGET: usize = 8;
+    pub const LOD_DISTANCE: i32 = 6;
+    /// Jobs per worker thread sent ahead, the rest waits in the queue to be reordered
+    const JOBS_PER_WORKER: usize = 2;
+
+    pub fn new(render_distance: i32, mesher: Mesher, device: &wgpu::Device) -> Self {
+        ChunkManager {
+            render_distance: render_distance
+                .clamp(Self::MIN_RENDER_DISTANCE, Self::MAX_RENDER_DISTANCE),
+            mesher,
+            upload_budget: Self::UPLOAD_BUDGET,
+            lod_distance: Some(Self::LOD_DISTANCE),
+            center: None,
+            chunks: HashMap::new(),
+            buffers: ChunkBuffers::new(device),
+            workers: WorkerPool::new(),
+            queue: LoadQueue::new(Viewer::new((0.0, 0.0).into(), (0.0, 0.0).into())),
+            in_flight: 0,
+            generating: HashMap::new(),
+            meshing: HashMap::new(),
+            unsent: HashSet::new(),
+            ready: VecDeque::new(),
+            loaded: false,
+            tickets: Tickets::default(),
+        }
+    }
+
+    /// Pins the chunks of a ticket as loaded until the ticket is removed
+    pub fn add_ticket(&mut self, ticket: LoadTicket) -> TicketId {
+        let id = self.tickets.add(ticket);
+        self.center = None;
+        id
+    }
+
+    /// Removes a ticket, its chunks are unloaded once nothing else keeps them
+    pub fn remove_ticket(&mut self, id: TicketId) -> Option<LoadTicket> {
+        let ticket = self.tickets.remove(id);
+        if ticket.is_some() {
+            self.center = None;
+        }
+        ticket
+    }
+
+    pub fn tickets(&self) -> &Tickets {
+        &self.tickets
+    }
+
+    /// `true` if a ticket keeps the chunk loaded
+    pub fn is_pinned(&self, pos: ChunkPos) -> bool {
+        self.tickets.pins(pos)
+    }
+
+    /// `true` if the blocks of a chunk stay in the world with the camera in `center`:
+    /// the chunk is within the render distance and one more ring of neighbours,
+    /// or a ticket pins it
+    pub fn keeps_blocks(
+        center: ChunkPos,
+        render_distance: i32,
+        tickets: &Tickets,
+        pos: ChunkPos,
+    ) -> bool {
+        Self::distance(center, pos) <= render_distance + 1 || tickets.pins(pos)
+    }
+
+    pub fn render_distance(&self) -> i32 {
+        self.render_distance
+    }
+
+    /// Changes the render distance, chunks are loaded or unloaded on the next update
+    pub fn set_render_distance(&mut self, render_distance: i32) {
+        let render_distance =
+            render_distance.clamp(Self::MIN_RENDER_DISTANCE, Self::MAX_RENDER_DISTANCE);
+        if render_distance != self.render_distance {
+            log::info!("render distance {}", render_distance);
+            self.render_distance = render_distance;
+            self.center = None;
+        }
+    }
+
+    pub fn lod_distance(&self) -> Option<i32> {
+        self.lod_distance
+    }
+
+    /// Changes the level of detail distance, chunks are meshed again on the next update
+    pub fn set_lod_distance(&mut self, lod_distance: Option<i32>) {
+        if lod_distance != self.lod_distance {
+            log::info!("level of detail distance {:?}", lod_distance);
+            self.lod_distance = lod_distance;
+            self.center = None;
+        }
+    }
+
+    /// Chunk distance in the xz plane, chunks within a distance form a square
+    pub fn distance(a: ChunkPos, b: ChunkPos) -> i32 {
+        (a.x - b.x).abs().max((a.z - b.z).abs())
+    }
+
+    /// Every chunk within a distance from the center
+    pub fn around(center: ChunkPos, distance: i32) -> impl Iterator<Item = ChunkPos> {
+        (-distance..=distance)
+            .flat_map(move |dx| (-distance..=distance).map(move |dz| center.offset(dx, dz)))
+    }
+
+    /// Streams the chunks around the camera, called every frame.
+    /// When the camera moves into another chunk, jobs for the chunks which left the render
+    /// distance are cancelled and the chunks themselves are dropped
+    pub fn update(
+        &mut self,
+        world: &mut World,
+        camera: &Camera,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) {
+        let center = BlockPos::containing(camera.position.to_vec()).chunk();
+        self.queue.set_viewer(Viewer::from(camera));
+        if self.center != Some(center) {
+            self.center = Some(center);
+            self.recenter(world, center);
+        }
+        while let Some(result) = self.workers.try_recv() {
+            self.in_flight -= 1;
+            match result.output {
+                JobOutput::Generated(blocks) => {
+                    if Self::is_current(&self.generating, result.pos, &result.handle) {
+                        self.generating.remove(&result.pos);
+                        world.insert_chunk(result.pos, blocks);
+                        self.loaded = true;
+                    }
+                }
+                JobOutput::Meshed(..) => {
+                    if Self::is_current(&self.meshing, result.pos, &result.handle) {
+                        self.ready.push_back(result);
+                    }
+                }
+                JobOutput::Cancelled => {}
+            }
+        }
+        if self.loaded {
+            self.loaded = false;
+            self.queue_meshes(world, center);
+        }
+        self.dispatch(world);
+        self.upload(device, queue);
+    }
+
+    /// `true` if a result belongs to the latest job of its chunk and the job was not cancelled
+    fn is_current(jobs: &HashMap<ChunkPos, JobHandle>, pos: ChunkPos, handle: &JobHandle) -> bool {
+        !handle.is_cancelled() && jobs.get(&pos).is_some_and(|job| job.same(handle))
+    }
+
+    /// Drops everything out of the render distance around a new center and not pinned
+    /// by a ticket, then queues loading of the chunks which came into it
+    fn recenter(&mut self, world: &mut World, center: ChunkPos) {
+        let distance = self.render_distance;
+        let in_range = |pos: &ChunkPos, distance| Self::distance(center, *pos) <= distance;
+        let tickets = &self.tickets;
+        let wanted = |pos: &ChunkPos| Self::keeps_blocks(center, distance, tickets, *pos);
+        self.generating.retain(|pos, handle| {
+            let keep = wanted(pos);
+            if !keep {
+                handle.cancel();
+            }
+            keep
+        });
+        self.meshing.retain(|pos, handle| {
+            let keep = in_range(pos, distance);
+            if !keep {
+                handle.cancel();
+            }
+            keep
+        });
+        let meshing = &self.meshing;
+        self.unsent.retain(|pos| meshing.contains_key(pos));
+        self.ready.retain(|result| in_range(&result.pos, distance));
+        let buffers = &mut self.buffers;
+        self.chunks.retain(|pos, chunk| {
+            let keep = in_range(pos, distance);
+            if !keep {
+                chunk.free(buffers);
+            }
+            keep
+        });
+        let far: Vec<_> = world
+            .chunks()
+            .keys()
+            .filter(|pos| !wanted(pos))
+            .cloned()
+            .collect();
+        for pos in far {
+            if let Err(e) = world.unload_chunk(pos) {
+                log::error!("can not unload chunk {:?}: {:?}", pos, e);
+            }
+        }
+
+        let mut generating = 0;
+        let pinned: Vec<_> = self.tickets.chunks().collect();
+        for pos in Self::around(center, distance + 1).chain(pinned) {
+            if world.chunk(pos).is_some() || self.generating.contains_key(&pos) {
+                continue;
+            }
+            match world.load_chunk(pos) {
+                Ok(true) => continue,
+                Ok(false) => {}
+                Err(e) => log::error!(
+                    "can not read chunk {:?}, it is generated again: {:?}",
+                    pos,
+                    e
+                ),
+            }
+            let handle = JobHandle::default();
+            self.queue.push(pos, Work::Generate, handle.clone());
+            self.generating.insert(pos, handle);
+            generating += 1;
+        }
+        let moved: Vec<_> = self
+            .chunks
+            .values()
+            .filter(|chunk| chunk.lod != self.lod(chunk.coords))
+            .map(|chunk| chunk.coords)
+            .collect();
+        for pos in moved {
+            self.queue_remesh(pos);
+        }
+        // chunks read from the save can be meshed right away
+        self.loaded = true;
+        log::debug!(
+            "camera in chunk {:?}, {} chunks drawn, {} chunks loaded, {} chunks to generate",
+            center,
+            self.chunks.len(),
+            world.chunks().len(),
+            generating
+        );
+    }
+
+    /// Queues meshing of the chunks in the render distance with all of their neighbours loaded
+    fn queue_meshes(&mut self, world: &World, center: ChunkPos) {
+        for pos in Self::around(center, self.render_distance) {
+            if self.chunks.contains_key(&pos) || self.meshing.contains_key(&pos) {
+                continue;
+            }
+            if Self::around(pos, 1).any(|pos| world.chunk(pos).is_none()) {
+                continue;
+            }
+            self.queue_mesh(pos, self.mesher);
+        }
+    }
+
+    fn queue_mesh(&mut self, pos: ChunkPos, mesher: Mesher) {
+        let handle = JobHandle::default();
+        self.queue.push(pos, Work::Mesh(mesher), handle.clone());
+        self.meshing.insert(pos, handle);
+        self.unsent.insert(pos);
+    }
+
+    /// Blocks of a chunk and its neighbours, `None` if one of them is not loaded
+    fn neighbour_blocks(world: &World, pos: ChunkPos) -> Option<NeighbourBlocks> {
+        let mut blocks: NeighbourBlocks = Default::default();
+        for dx in -1..=1 {
+            for dz in -1..=1 {
+                let chunk = world.chunk(pos.offset(dx, dz))?.clone();
+                blocks[(dx + 1) as usize][(dz + 1) as usize] = Some(chunk);
+            }
+        }
+        Some(blocks)
+    }
+
+    /// Sends the most urgent queued work to the workers. Only a few jobs per worker
+    /// are sent ahead, so the queue can still be reordered when the camera turns
+    fn dispatch(&mut self, world: &World) {
+        while self.in_flight < self.workers.threads() * Self::JOBS_PER_WORKER {
+            let queued = match self.queue.pop() {
+                Some(queued) => queued,
+                None => break,
+            };
+            if let Work::Mesh(_) = queued.work {
+                self.unsent.remove(&queued.pos);
+            }
+            let kind = match queued.work {
+                Work::Generate => JobKind::Generate { seed: world.seed() },
+                // blocks are copied as late as possible, they may have changed in the queue
+                Work::Mesh(mesher) => match Self::neighbour_blocks(world, queued.pos) {
+                    Some(blocks) => JobKind::Mesh {
+                        blocks,
+                        mesher,
+                        lod: self.lod(queued.pos),
+                    },
+                    None => {
+                        self.meshing.remove(&queued.pos);
+                        continue;
+                    }
+                },
+            };
+            self.workers.send(Job {
+                pos: queued.pos,
+                kind,
+                handle: queued.handle,
+            });
+            self.in_flight += 1;
+        }
+    }
+
+    /// Uploads meshes built by the workers, up to the upload budget
+    fn upload(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        let mut uploaded = 0;
+        while uploaded < self.upload_budget {
+            let result = match self.ready.pop_front() {
+                Some(result) => result,
+                None => break,
+            };
+            if !Self::is_current(&self.meshing, result.pos, &result.handle) {
+                continue;
+            }
+            self.meshing.remove(&result.pos);
+            if let JobOutput::Meshed(mesher, mesh) = result.output {
+                // a chunk without room is meshed again once chunks out of range free some
+                if let Some(chunk) =
+                    Chunk::from_mesh(result.pos, mesher, mesh, &mut self.buffers, device, queue)
+                {
+                    // the camera may have moved it to another level since the job was sent
+                    let moved = chunk.lod != self.lod(result.pos);
+                    self.insert(chunk);
+                    if moved {
+                        self.queue_remesh(result.pos);
+                    }
+                }
+                uploaded += 1;
+            }
+        }
+    }
+
+    /// Adds a meshed chunk, the mesh of the chunk it replaces is freed
+    fn insert(&mut self, chunk: Chunk) {
+        if let Some(old) = self.chunks.insert(chunk.coords, chunk) {
+            old.free(&mut self.buffers);
+        }
+    }
+
+    /// Cancels the mesh job of a chunk, the chunk is sent to meshing again
+    fn cancel_mesh(&mut self, pos: ChunkPos) {
+        if let Some(handle) = self.meshing.remove(&pos) {
+            handle.cancel();
+            self.loaded = true;
+        }
+    }
+
+    /// Re-builds the mesh of a meshed chunk with its own mesher and level of detail right away,
+    /// a mesh still being built from the old blocks is thrown away.
+    /// The old mesh stays when the buffers have no room for the new one
+    pub fn remesh(
+        &mut self,
+        world: &World,
+        pos: ChunkPos,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) {
+        self.cancel_mesh(pos);
+        let lod = self.lod(pos);
+        if let Some(chunk) = self.chunks.get(&pos) {
+            if let Some(chunk) = Chunk::new(
+                pos,
+                world,
+                chunk.mesher,
+                lod,
+                &mut self.buffers,
+                device,
+                queue,
+            ) {
+                self.insert(chunk);
+            }
+        }
+    }
+
+    /// Re-builds the mesh of a meshed chunk on the workers, the chunk is drawn with its
+    /// current mesh until the new one is uploaded. For changes nobody waits for, like the
+    /// ones of the world ticks, while [`ChunkManager::remesh`] is for the edits of the player
+    pub fn queue_remesh(&mut self, pos: ChunkPos) {
+        let mesher = match self.chunks.get(&pos) {
+            Some(chunk) => chunk.mesher,
+            None => return,
+        };
+        if self.meshing.contains_key(&pos) && self.unsent.contains(&pos) {
+            // the queued work copies the changed blocks anyway
+            return;
+        }
+        if let Some(handle) = self.meshing.remove(&pos) {
+            handle.cancel();
+        }
+        self.queue_mesh(pos, mesher);
+    }
+
+    /// Switches every chunk to a mesher and re-builds all the meshes
+    pub fn set_mesher(
+        &mut self,
+        mesher: Mesher,
+        world: &World,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) {
+        self.mesher = mesher;
+        let meshing: Vec<_> = self.meshing.keys().cloned().collect();
+        for pos in meshing {
+            self.cancel_mesh(pos);
+        }
+        let chunks: Vec<_> = self.chunks.keys().cloned().collect();
+        for pos in chunks {
+            let lod = self.lod(pos);
+            if let Some(chunk) =
+                Chunk::new(pos, world, mesher, lod, &mut self.buffers, device, queue)
+            {
+                self.insert(chunk);
+            }
+        }
+    }
+
+    /// Level of detail a chunk is drawn with from the chunk of the camera
+    pub fn lod(&self, pos: ChunkPos) -> Lod {
+        match (self.lod_distance, self.center) {
+            (Some(lod_distance), Some(center)) => {
+                Lod::for_distance(Self::distance(center, pos), lod_distance)
+            }
+            _ => Lod::FULL,
+        }
+    }
+
+    pub fn get(&self, pos: ChunkPos) -> Option<&Chunk> {
+        self.chunks.get(&pos)
+    }
+
+    /// Meshed chunks which can be seen from the camera through open space, see [`visibility`]
+    pub fn visible(&self, camera: &Camera) -> HashSet<ChunkPos> {
+        let camera = BlockPos::containing(camera.position.to_vec());
+        visibility::visible_chunks(camera, self.render_distance, |pos| {
+            self.chunks.get(&pos).map(|chunk| &chunk.visibility)
+        })
+    }
+
+    /// Chunks queued or being generated or meshed
+    pub fn pending(&self) -> usize {
+        self.generating.len() + self.meshing.len()
+    }
+
+    /// Meshed chunks
+    pub fn iter(&self) -> impl Iterator<Item = &Chunk> {
+        self.chunks.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+}
@@ -0,0 +1,305 @@
+use crate::block::{registry, RenderType};
+use crate::coords::{BlockPos, LocalPos};
+use crate::vertex::ChunkVertex;
+use crate::voxel::Voxel;
+
+use super::lod::{Downsampled, Lod};
+use super::neighbours::ChunkNeighbours;
+use super::storage::ChunkStorage;
+use super::visibility::ChunkVisibility;
+use super::Chunk;
+
+/// Vertices and indices of a chunk, the material is a texture array layer stored in every vertex
+#[derive(Debug, Default)]
+pub struct ChunkMesh {
+    pub vertices: Vec<ChunkVertex>,
+    /// Indices of the faces of every [`RenderType`], they are drawn by different pipelines
+    pub indices: [Vec<u32>; RenderType::COUNT],
+    /// Cave culling graph of the blocks the mesh was built from
+    pub visibility: ChunkVisibility,
+    /// Level of detail the mesh was built at
+    pub lod: Lod,
+}
+
+impl ChunkMesh {
+    pub fn quads(&self) -> usize {
+        self.indices.iter().map(Vec::len).sum::<usize>() / 6
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.iter().all(Vec::is_empty)
+    }
+
+    /// Adds a quad, it is split along the diagonal with the darker corners,
+    /// otherwise ambient occlusion is interpolated unevenly across the quad
+    fn push_quad(&mut self, quad: [ChunkVertex; 4], render: RenderType) {
+        let idx = self.vertices.len() as u32;
+        self.vertices.extend_from_slice(&quad);
+        let order = if quad[0].ao() + quad[2].ao() > quad[1].ao() + quad[3].ao() {
+            [1, 2, 3, 3, 0, 1]
+        } else {
+            [0, 1, 2, 2, 3, 0]
+        };
+        self.indices[render.index()].extend(order.iter().map(|i| i + idx));
+    }
+}
+
+/// A visible voxel face, faces are merged only if they are equal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Face {
+    /// Texture array layer
+    material_id: usize,
+    /// Corner ambient occlusion in [`Voxel::FACES`] vertex order
+    ao: [u8; 4],
+    render: RenderType,
+    /// The top edge is lowered, the face belongs to a liquid with neither the same liquid
+    /// nor an opaque block above
+    lowered: bool,
+}
+
+/// How the surface of a chunk is turned into quads
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Mesher {
+    /// One quad per visible voxel face
+    #[default]
+    PerFace,
+    /// Coplanar visible faces of the same material are merged into larger quads
+    Greedy,
+}
+
+impl Mesher {
+    /// Axis along the normal of each face in [`Voxel::FACES`] order
+    const NORMAL_AXIS: [usize; 6] = [2, 2, 1, 1, 0, 0];
+    /// Axes the `u` and `v` texture coordinates of each face in [`Voxel::FACES`] run along
+    const UV_AXES: [(usize, usize); 6] = [(0, 1), (0, 1), (0, 2), (0, 2), (2, 1), (2, 1)];
+    const SIZE: [usize; 3] = [Chunk::WIDTH, Chunk::HEIGHT, Chunk::WIDTH];
+    /// Index of the bottom face in [`Voxel::FACES`]
+    const BOTTOM: usize = 3;
+
+    pub fn toggle(self) -> Self {
+        match self {
+            Mesher::PerFace => Mesher::Greedy,
+            Mesher::Greedy => Mesher::PerFace,
+        }
+    }
+
+    /// The full mesh of a chunk
+    pub fn build(&self, neighbours: &ChunkNeighbours) -> ChunkMesh {
+        self.build_at(neighbours, Lod::FULL)
+    }
+
+    /// The mesh of a chunk at a level of detail, coarse levels are always merged greedily
+    pub fn build_at(&self, neighbours: &ChunkNeighbours, lod: Lod) -> ChunkMesh {
+        let mut mesh = match (self, neighbours.center()) {
+            (_, Some(blocks)) if lod != Lod::FULL => Self::build_lod(blocks, lod),
+            (Mesher::PerFace, _) => Self::per_face(neighbours),
+            (Mesher::Greedy, _) => Self::greedy(neighbours),
+        };
+        if let Some(blocks) = neighbours.center() {
+            mesh.visibility = ChunkVisibility::compute(blocks);
+        }
+        mesh
+    }
+
+    /// Greedy mesh of the blocks of a chunk merged into voxels of a level of detail.
+    /// Faces on the chunk borders are never culled, so the side walls of the chunk
+    /// cover the cracks next to chunks of a finer level, whose surface is never higher
+    pub fn build_lod(blocks: &ChunkStorage, lod: Lod) -> ChunkMesh {
+        let voxels = Downsampled::new(blocks, lod);
+        let mut mesh = Self::merge(voxels.size, lod.scale(), |pos, face_id| {
+            Self::lod_face(&voxels, pos, face_id)
+        });
+        mesh.lod = lod;
+        mesh
+    }
+
+    /// A visible face of a downsampled voxel, ambient occlusion is left out
+    fn lod_face(voxels: &Downsampled, pos: [i32; 3], face_id: usize) -> Option<Face> {
+        let registry = registry();
+        let kind = voxels.get(pos)?;
+        if !registry.is_visible(kind) {
+            return None;
+        }
+        let check = Voxel::FACE_CHECK[face_id];
+        let check_pos = [0, 1, 2].map(|i| pos[i] + check[i] as i32);
+        if let Some(neighbour) = voxels.get(check_pos) {
+            if registry.is_opaque(neighbour) || neighbour == kind {
+                return None;
+            }
+        }
+        Some(Face {
+            material_id: registry.face_layer(kind, face_id),
+            ao: [3; 4],
+            render: registry.render_type(kind),
+            lowered: false,
+        })
+    }
+
+    /// A voxel face which has to be drawn, `None` if the block is invisible or the face
+    /// is hidden by a neighbour. Faces between two blocks of the same transparent kind,
+    /// like glass, are hidden as well
+    fn visible_face(neighbours: &ChunkNeighbours, pos: BlockPos, face_id: usize) -> Option<Face> {
+        let registry = registry();
+        let kind = neighbours.get(pos);
+        if !registry.is_visible(kind) {
+            return None;
+        }
+        let check_pos = pos + BlockPos::containing(Voxel::FACE_CHECK[face_id]);
+        if Chunk::check_voxel(check_pos, neighbours) {
+            return None;
+        }
+        let neighbour = neighbours.get(check_pos);
+        if neighbour == kind {
+            return None;
+        }
+        let above = neighbours.get(pos + BlockPos::new(0, 1, 0));
+        let lowered = registry.is_liquid(kind)
+            && face_id != Self::BOTTOM
+            && above != kind
+            && !registry.is_opaque(above);
+        Some(Face {
+            material_id: registry.face_layer(kind, face_id),
+            ao: Self::face_ao(neighbours, pos, face_id),
+            render: registry.render_type(kind),
+            lowered,
+        })
+    }
+
+    /// Classic voxel ambient occlusion of the face corners, in [`Voxel::FACES`] vertex order.
+    /// Each corner looks at the two side voxels and the corner voxel in front of the face,
+    /// the value goes from 0 (fully occluded) to 3 (open)
+    fn face_ao(neighbours: &ChunkNeighbours, pos: BlockPos, face_id: usize) -> [u8; 4] {
+        let n = Self::NORMAL_AXIS[face_id];
+        let (a, b) = Self::UV_AXES[face_id];
+        let mut layer: [i32; 3] = pos.into();
+        layer[n] += Voxel::FACE_CHECK[face_id][n] as i32;
+        let solid = |offset_a: i32, offset_b: i32| {
+            let mut p = layer;
+            p[a] += offset_a;
+            p[b] += offset_b;
+            neighbours.is_opaque(p.into()) as u8
+        };
+        Voxel::FACES[face_id].map(|vertex| {
+            let da = if vertex.position[a] > 0.5 { 1 } else { -1 };
+            let db = if vertex.position[b] > 0.5 { 1 } else { -1 };
+            let (side1, side2, corner) = (solid(da, 0), solid(0, db), solid(da, db));
+            if side1 == 1 && side2 == 1 {
+                0
+            } else {
+                3 - (side1 + side2 + corner)
+            }
+        })
+    }
+
+    /// Builds a quad of `w x h` voxel faces with the origin voxel at `d` along the normal,
+    /// `u` and `v` along the texture axes. Voxels are `scale` blocks wide
+    fn quad(
+        face_id: usize,
+        [d, u, v]: [usize; 3],
+        [w, h]: [usize; 2],
+        scale: usize,
+        face: &Face,
+    ) -> [ChunkVertex; 4] {
+        let n = Self::NORMAL_AXIS[face_id];
+        let (ua, va) = Self::UV_AXES[face_id];
+        let mut quad = [ChunkVertex::default(); 4];
+        for (i, vertex) in Voxel::FACES[face_id].iter().enumerate() {
+            let mut position = [0; 3];
+            position[n] = ((d + vertex.position[n] as usize) * scale) as u32;
+            position[ua] = ((u + vertex.position[ua] as usize * w) * scale) as u32;
+            position[va] = ((v + vertex.position[va] as usize * h) * scale) as u32;
+            let tex_coords = [
+                (vertex.tex_coords[0] as usize * w * scale) as u32,
+                (vertex.tex_coords[1] as usize * h * scale) as u32,
+            ];
+            quad[i] = ChunkVertex::new(position, face_id, tex_coords, face.material_id, face.ao[i])
+                .with_lowered(face.lowered && vertex.position[1] > 0.5);
+        }
+        quad
+    }
+
+    fn per_face(neighbours: &ChunkNeighbours) -> ChunkMesh {
+        let mut mesh = ChunkMesh::default();
+        for local in LocalPos::all() {
+            let pos = [local.x, local.y, local.z];
+            for face_id in 0..Voxel::FACES.len() {
+                if let Some(face) = Self::visible_face(neighbours, local.into(), face_id) {
+                    let n = Self::NORMAL_AXIS[face_id];
+                    let (ua, va) = Self::UV_AXES[face_id];
+                    let origin = [pos[n], pos[ua], pos[va]];
+                    let quad = Self::quad(face_id, origin, [1, 1], 1, &face);
+                    mesh.push_quad(quad, face.render);
+                }
+            }
+        }
+        mesh
+    }
+
+    fn greedy(neighbours: &ChunkNeighbours) -> ChunkMesh {
+        Self::merge(Self::SIZE, 1, |pos, face_id| {
+            Self::visible_face(neighbours, pos.into(), face_id)
+        })
+    }
+
+    /// Sweeps every slice of a grid of `size` voxels along each face normal and merges
+    /// rectangles of visible faces with the same material and ambient occlusion.
+    /// Texture coordinates grow with the quad, so a texture repeats once per block
+    fn merge<F>(size: [usize; 3], scale: usize, visible_face: F) -> ChunkMesh
+    where
+        F: Fn([i32; 3], usize) -> Option<Face>,
+    {
+        let mut mesh = ChunkMesh::default();
+        for face_id in 0..Voxel::FACES.len() {
+            let n = Self::NORMAL_AXIS[face_id];
+            let (ua, va) = Self::UV_AXES[face_id];
+            let (size_u, size_v) = (size[ua], size[va]);
+            let mut mask = vec![None; size_u * size_v];
+            for d in 0..size[n] {
+                for v in 0..size_v {
+                    for u in 0..size_u {
+                        let mut pos = [0; 3];
+                        pos[n] = d as i32;
+                        pos[ua] = u as i32;
+                        pos[va] = v as i32;
+                        mask[v * size_u + u] = visible_face(pos, face_id);
+                    }
+                }
+                for v in 0..size_v {
+                    let mut u = 0;
+                    while u < size_u {
+                        let face = match mask[v * size_u + u] {
+                            Some(face) => face,
+                            None => {
+                                u += 1;
+                                continue;
+                            }
+                        };
+                        let mut w = 1;
+                        while u + w < size_u && mask[v * size_u + u + w] == Some(face) {
+                            w += 1;
+                        }
+                        let mut h = 1;
+                        'grow: while v + h < size_v {
+                            for k in u..u + w {
+                                if mask[(v + h) * size_u + k] != Some(face) {
+                                    break 'grow;
+                                }
+                            }
+                            h += 1;
+                        }
+                        for dv in v..v + h {
+                            for du in u..u + w {
+                                mask[dv * size_u + du] = None;
+                            }
+                        }
+
+                        let quad = Self::quad(face_id, [d, u, v], [w, h], scale, &face);
+                        mesh.push_quad(quad, face.render);
+                        u += w;
+                    }
+                }
+            }
+        }
+        mesh
+    }
+}
@@ -0,0 +1,69 @@
+use crate::block::BlockKind;
+use crate::coords::{BlockPos, ChunkPos};
+use crate::world::World;
+
+use super::storage::ChunkStorage;
+
+/// A chunk together with the 8 chunks around it.
+///
+/// The mesher looks up voxels through it, so faces between two chunks are
+/// culled the same way as faces inside a chunk. A neighbour which is not
+/// loaded reads as air, its border faces are drawn until it appears.
+#[derive(Debug)]
+pub struct ChunkNeighbours<'a> {
+    /// Chunks indexed by `[dx + 1][dz + 1]`
+    chunks: [[Option<&'a ChunkStorage>; 3]; 3],
+}
+
+impl<'a> ChunkNeighbours<'a> {
+    pub fn new(world: &'a World, pos: ChunkPos) -> Self {
+        let mut chunks = [[None; 3]; 3];
+        for dx in -1..=1 {
+            for dz in -1..=1 {
+                chunks[(dx + 1) as usize][(dz + 1) as usize] = world.chunk(pos.offset(dx, dz));
+            }
+        }
+        ChunkNeighbours { chunks }
+    }
+
+    /// Chunks indexed by `[dx + 1][dz + 1]`, the center chunk is `[1][1]`
+    pub fn from_chunks(chunks: [[Option<&'a ChunkStorage>; 3]; 3]) -> Self {
+        ChunkNeighbours { chunks }
+    }
+
+    /// A chunk without neighbours, every voxel outside of it reads as air
+    pub fn single(chunk: &'a ChunkStorage) -> Self {
+        let mut chunks = [[None; 3]; 3];
+        chunks[1][1] = Some(chunk);
+        ChunkNeighbours { chunks }
+    }
+
+    pub fn center(&self) -> Option<&'a ChunkStorage> {
+        self.chunks[1][1]
+    }
+
+    /// Block at a position relative to the first block of the center chunk,
+    /// it may point up to one chunk outside of it
+    pub fn get(&self, pos: BlockPos) -> BlockKind {
+        let (chunk, local) = match pos.split() {
+            Some(split) => split,
+            None => return BlockKind::AIR,
+        };
+        if chunk.x.abs() > 1 || chunk.z.abs() > 1 {
+            return BlockKind::AIR;
+        }
+        match self.chunks[(chunk.x + 1) as usize][(chunk.z + 1) as usize] {
+            Some(chunk) => chunk.get_local(local),
+            None => BlockKind::AIR,
+        }
+    }
+
+    /// An opaque block hides the faces next to it and darkens their corners
+    pub fn is_opaque(&self, pos: BlockPos) -> bool {
+        self.get(pos).is_opaque()
+    }
+
+    pub fn is_solid(&self, pos: BlockPos) -> bool {
+        self.get(pos).is_solid()
+    }
+}
@@ -0,0 +1,172 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use cgmath::{EuclideanSpace, InnerSpace, Vector2};
+
+use crate::camera::Camera;
+use crate::coords::ChunkPos;
+
+use super::mesher::Mesher;
+use super::worker::JobHandle;
+use super::Chunk;
+
+/// Work on a chunk waiting for a free worker
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Work {
+    Generate,
+    Mesh(Mesher),
+}
+
+/// Where the camera is and where it looks, in the xz plane
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewer {
+    pub position: Vector2<f32>,
+    /// Normalized look direction, zero when looking straight up or down
+    pub direction: Vector2<f32>,
+}
+
+impl Viewer {
+    /// A viewer turned by less than this angle keeps the queue order
+    const TURN_COS: f32 = 0.97;
+
+    pub fn new(position: Vector2<f32>, direction: Vector2<f32>) -> Self {
+        let direction = if direction.magnitude2() > 1e-6 {
+            direction.normalize()
+        } else {
+            Vector2::new(0.0, 0.0)
+        };
+        Viewer {
+            position,
+            direction,
+        }
+    }
+
+    /// Priority of work on a chunk, the lower the sooner.
+    /// It is the distance from the viewer to the chunk center in chunks,
+    /// doubled for chunks right behind the viewer. The chunk of the viewer and
+    /// the chunks around it come first whatever the direction is
+    pub fn priority(&self, pos: ChunkPos) -> f32 {
+        let width = Chunk::WIDTH as f32;
+        let center = Vector2::new(pos.x as f32 + 0.5, pos.z as f32 + 0.5) * width;
+        let to_chunk = (center - self.position) / width;
+        let distance = to_chunk.magnitude();
+        if distance < 1.5 {
+            return distance;
+        }
+        let facing = self.direction.dot(to_chunk / distance);
+        distance * (1.5 - 0.5 * facing)
+    }
+
+    /// `true` if the viewer moved into another chunk or turned enough to reorder the queue
+    pub fn differs(&self, other: &Viewer) -> bool {
+        let width = Chunk::WIDTH as f32;
+        let chunk = |v: &Viewer| (v.position / width).map(f32::floor);
+        let turned = self.direction != other.direction
+            && self.direction.dot(other.direction) < Self::TURN_COS;
+        chunk(self) != chunk(other) || turned
+    }
+}
+
+impl From<&Camera> for Viewer {
+    fn from(camera: &Camera) -> Self {
+        let position = camera.position.to_vec();
+        let direction = camera.look_at_coord();
+        Viewer::new(
+            Vector2::new(position.x, position.z),
+            Vector2::new(direction.x, direction.z),
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct QueuedWork {
+    pub pos: ChunkPos,
+    pub work: Work,
+    pub handle: JobHandle,
+    priority: f32,
+}
+
+impl PartialEq for QueuedWork {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for QueuedWork {}
+
+impl PartialOrd for QueuedWork {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// The work with the lowest priority is the greatest one, it is on top of the heap
+impl Ord for QueuedWork {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.priority.total_cmp(&self.priority)
+    }
+}
+
+/// Chunk work ordered by [`Viewer::priority`], the closest chunks in front of the
+/// camera come out first. The order is updated when the viewer moves or turns
+#[derive(Debug)]
+pub struct LoadQueue {
+    heap: BinaryHeap<QueuedWork>,
+    viewer: Viewer,
+}
+
+impl LoadQueue {
+    pub fn new(viewer: Viewer) -> Self {
+        LoadQueue {
+            heap: BinaryHeap::new(),
+            viewer,
+        }
+    }
+
+    pub fn push(&mut self, pos: ChunkPos, work: Work, handle: JobHandle) {
+        let priority = self.viewer.priority(pos);
+        self.heap.push(QueuedWork {
+            pos,
+            work,
+            handle,
+            priority,
+        });
+    }
+
+    /// The most urgent work which was not cancelled
+    pub fn pop(&mut self) -> Option<QueuedWork> {
+        while let Some(queued) = self.heap.pop() {
+            if !queued.handle.is_cancelled() {
+                return Some(queued);
+            }
+        }
+        None
+    }
+
+    /// Reorders the queue for a new viewer, cancelled work is dropped on the way
+    pub fn set_viewer(&mut self, viewer: Viewer) {
+        if !viewer.differs(&self.viewer) {
+            return;
+        }
+        self.viewer = viewer;
+        let mut queued = std::mem::take(&mut self.heap).into_vec();
+        queued.retain(|queued| !queued.handle.is_cancelled());
+        for queued in queued.iter_mut() {
+            queued.priority = viewer.priority(queued.pos);
+        }
+        self.heap = BinaryHeap::from(queued);
+    }
+
+    pub fn viewer(&self) -> &Viewer {
+        &self.viewer
+    }
+
+    /// Queued work including the cancelled one which was not popped yet
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+}
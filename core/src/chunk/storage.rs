@@ -0,0 +1,200 @@
+use std::collections::BTreeMap;
+
+use crate::block::BlockKind;
+use crate::coords::LocalPos;
+
+use super::Chunk;
+
+/// Palette compressed block storage of a chunk.
+///
+/// Every distinct block of a chunk is kept once in `palette` and voxels only
+/// keep `bits` wide indices into it, packed into `u64` words. An index never
+/// crosses a word boundary, so `64 / bits` indices fit into a word.
+/// A chunk of a single block kind has zero bit indices and keeps no data at all.
+///
+/// Flowing liquids have a level next to their block, see [`crate::world::fluid`].
+/// Only the few voxels with a level keep one, every other voxel is level 0.
+#[derive(Debug, Clone)]
+pub struct ChunkStorage {
+    palette: Vec<BlockKind>,
+    bits: u32,
+    data: Vec<u64>,
+    /// Non-zero levels by voxel index
+    levels: BTreeMap<u16, u8>,
+}
+
+impl ChunkStorage {
+    /// Number of voxels in a chunk
+    pub const LEN: usize = Chunk::WIDTH * Chunk::HEIGHT * Chunk::WIDTH;
+
+    /// A chunk filled with a single block kind
+    pub fn filled(kind: BlockKind) -> Self {
+        ChunkStorage {
+            palette: vec![kind],
+            bits: 0,
+            data: Vec::new(),
+            levels: BTreeMap::new(),
+        }
+    }
+
+    pub fn get(&self, i: usize) -> BlockKind {
+        debug_assert!(i < Self::LEN);
+        if self.bits == 0 {
+            return self.palette[0];
+        }
+        let per_word = Self::per_word(self.bits);
+        let word = self.data[i / per_word];
+        let shift = (i % per_word) as u32 * self.bits;
+        let id = (word >> shift) & Self::mask(self.bits);
+        self.palette[id as usize]
+    }
+
+    /// Sets the block of a voxel, its level goes back to 0
+    pub fn set(&mut self, i: usize, kind: BlockKind) {
+        debug_assert!(i < Self::LEN);
+        if !self.levels.is_empty() {
+            self.levels.remove(&(i as u16));
+        }
+        let id = match self.palette.iter().position(|k| *k == kind) {
+            Some(id) => id,
+            None => {
+                self.palette.push(kind);
+                let needed = Self::bits_for(self.palette.len());
+                if needed > self.bits {
+                    self.resize(needed);
+                }
+                self.palette.len() - 1
+            }
+        };
+        if self.bits == 0 {
+            return;
+        }
+        let per_word = Self::per_word(self.bits);
+        let shift = (i % per_word) as u32 * self.bits;
+        let word = &mut self.data[i / per_word];
+        *word = (*word & !(Self::mask(self.bits) << shift)) | ((id as u64) << shift);
+    }
+
+    pub fn get_local(&self, pos: LocalPos) -> BlockKind {
+        self.get(pos.index())
+    }
+
+    pub fn set_local(&mut self, pos: LocalPos, kind: BlockKind) {
+        self.set(pos.index(), kind)
+    }
+
+    /// Level of a voxel, 0 unless it was set with [`ChunkStorage::set_level`]
+    pub fn level(&self, i: usize) -> u8 {
+        debug_assert!(i < Self::LEN);
+        self.levels.get(&(i as u16)).copied().unwrap_or(0)
+    }
+
+    /// Sets the level of a voxel, it is kept until the block of the voxel is set
+    pub fn set_level(&mut self, i: usize, level: u8) {
+        debug_assert!(i < Self::LEN);
+        if level == 0 {
+            self.levels.remove(&(i as u16));
+        } else {
+            self.levels.insert(i as u16, level);
+        }
+    }
+
+    pub fn level_local(&self, pos: LocalPos) -> u8 {
+        self.level(pos.index())
+    }
+
+    pub fn set_level_local(&mut self, pos: LocalPos, level: u8) {
+        self.set_level(pos.index(), level)
+    }
+
+    /// Voxel indices and levels of every voxel with a non-zero level, by index
+    pub fn levels(&self) -> impl Iterator<Item = (usize, u8)> + '_ {
+        self.levels.iter().map(|(i, level)| (*i as usize, *level))
+    }
+
+    pub fn palette(&self) -> &[BlockKind] {
+        &self.palette
+    }
+
+    /// Bits used to store a single voxel
+    pub fn bits(&self) -> u32 {
+        self.bits
+    }
+
+    /// Heap and inline bytes used by the storage
+    pub fn memory_size(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.palette.capacity() * std::mem::size_of::<BlockKind>()
+            + self.data.capacity() * std::mem::size_of::<u64>()
+            + self.levels.len() * std::mem::size_of::<(u16, u8)>()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = BlockKind> + '_ {
+        (0..Self::LEN).map(move |i| self.get(i))
+    }
+
+    pub fn to_vec(&self) -> Vec<BlockKind> {
+        self.iter().collect()
+    }
+
+    /// Repacks the indices with a new bit width
+    fn resize(&mut self, bits: u32) {
+        let mut data = vec![0u64; Self::words(bits)];
+        if self.bits > 0 {
+            let per_word = Self::per_word(bits);
+            for i in 0..Self::LEN {
+                let old_per_word = Self::per_word(self.bits);
+                let old_shift = (i % old_per_word) as u32 * self.bits;
+                let id = (self.data[i / old_per_word] >> old_shift) & Self::mask(self.bits);
+                data[i / per_word] |= id << ((i % per_word) as u32 * bits);
+            }
+        }
+        // with zero bits every voxel pointed to the first palette entry,
+        // which is index 0 in the new data as well
+        self.bits = bits;
+        self.data = data;
+    }
+
+    fn bits_for(palette_len: usize) -> u32 {
+        match palette_len {
+            0 | 1 => 0,
+            n => usize::BITS - (n - 1).leading_zeros(),
+        }
+    }
+
+    fn per_word(bits: u32) -> usize {
+        (u64::BITS / bits) as usize
+    }
+
+    fn words(bits: u32) -> usize {
+        let per_word = Self::per_word(bits);
+        Self::LEN.div_ceil(per_word)
+    }
+
+    fn mask(bits: u32) -> u64 {
+        (1u64 << bits) - 1
+    }
+}
+
+impl Default for ChunkStorage {
+    fn default() -> Self {
+        Self::filled(BlockKind::AIR)
+    }
+}
+
+impl PartialEq for ChunkStorage {
+    fn eq(&self, other: &Self) -> bool {
+        self.levels == other.levels && self.iter().eq(other.iter())
+    }
+}
+
+impl From<&[BlockKind]> for ChunkStorage {
+    fn from(blocks: &[BlockKind]) -> Self {
+        debug_assert_eq!(blocks.len(), Self::LEN);
+        let mut storage = ChunkStorage::filled(blocks.first().copied().unwrap_or_default());
+        for (i, kind) in blocks.iter().enumerate() {
+            storage.set(i, *kind);
+        }
+        storage
+    }
+}
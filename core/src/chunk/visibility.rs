@@ -0,0 +1,256 @@
+//! Cave culling.
+//!
+//! A chunk is split into sections of 16 blocks high. Every section keeps which of its
+//! faces can see each other through blocks which are not opaque, a flood fill finds them
+//! when the chunk is meshed. Each frame a breadth-first traversal starts at the section of
+//! the camera and only goes from a section to its neighbour when the face it entered
+//! through is connected to the face it leaves through, and never turns back towards
+//! the camera. Chunks with no section reached are hidden behind solid rock.
+use std::collections::{HashSet, VecDeque};
+
+use crate::coords::{BlockPos, ChunkPos, LocalPos};
+
+use super::storage::ChunkStorage;
+use super::Chunk;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Face {
+    /// -x
+    West,
+    /// +x
+    East,
+    /// -y
+    Down,
+    /// +y
+    Up,
+    /// -z
+    North,
+    /// +z
+    South,
+}
+
+impl Face {
+    pub const ALL: [Face; 6] = [
+        Face::West,
+        Face::East,
+        Face::Down,
+        Face::Up,
+        Face::North,
+        Face::South,
+    ];
+
+    pub fn opposite(self) -> Face {
+        match self {
+            Face::West => Face::East,
+            Face::East => Face::West,
+            Face::Down => Face::Up,
+            Face::Up => Face::Down,
+            Face::North => Face::South,
+            Face::South => Face::North,
+        }
+    }
+
+    /// Step to the neighbour section through the face
+    pub fn offset(self) -> [i32; 3] {
+        match self {
+            Face::West => [-1, 0, 0],
+            Face::East => [1, 0, 0],
+            Face::Down => [0, -1, 0],
+            Face::Up => [0, 1, 0],
+            Face::North => [0, 0, -1],
+            Face::South => [0, 0, 1],
+        }
+    }
+
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// Pairs of faces of a section which see each other, a bit per pair
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Connectivity(u64);
+
+impl Connectivity {
+    /// Every face sees every other face, a section of air for example
+    pub const OPEN: Connectivity = Connectivity((1 << 36) - 1);
+    /// No face sees another face, a section of solid rock for example
+    pub const CLOSED: Connectivity = Connectivity(0);
+
+    pub fn connects(&self, a: Face, b: Face) -> bool {
+        self.0 & Self::pair(a, b) != 0
+    }
+
+    fn connect(&mut self, a: Face, b: Face) {
+        self.0 |= Self::pair(a, b) | Self::pair(b, a);
+    }
+
+    fn pair(a: Face, b: Face) -> u64 {
+        1 << (a as u64 * 6 + b as u64)
+    }
+}
+
+/// Face connectivity of every section of a chunk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkVisibility {
+    sections: [Connectivity; Chunk::SECTIONS],
+}
+
+impl ChunkVisibility {
+    /// Flood fills the blocks which are not opaque in every section of a chunk
+    pub fn compute(blocks: &ChunkStorage) -> Self {
+        let mut sections = [Connectivity::CLOSED; Chunk::SECTIONS];
+        for (section, connectivity) in sections.iter_mut().enumerate() {
+            *connectivity = Self::flood(blocks, section);
+        }
+        ChunkVisibility { sections }
+    }
+
+    /// A chunk which never hides anything behind it
+    pub fn open() -> Self {
+        ChunkVisibility {
+            sections: [Connectivity::OPEN; Chunk::SECTIONS],
+        }
+    }
+
+    pub fn section(&self, section: usize) -> Connectivity {
+        self.sections[section]
+    }
+
+    fn flood(blocks: &ChunkStorage, section: usize) -> Connectivity {
+        let (w, h) = (Chunk::WIDTH, Chunk::SECTION_HEIGHT);
+        let base = section * h;
+        let index = |[x, y, z]: [usize; 3]| (y * w + z) * w + x;
+        let mut visited = vec![false; w * h * w];
+        let mut connectivity = Connectivity::CLOSED;
+        let mut stack = Vec::new();
+        for start in 0..visited.len() {
+            let start = [start % w, start / (w * w), (start / w) % w];
+            if visited[index(start)] || Self::is_opaque(blocks, start, base) {
+                continue;
+            }
+            visited[index(start)] = true;
+            stack.push(start);
+            let mut faces = 0u8;
+            while let Some(p @ [x, y, z]) = stack.pop() {
+                faces |= Self::touched(p, w, h);
+                for face in Face::ALL {
+                    let [dx, dy, dz] = face.offset();
+                    let next = [x as i32 + dx, y as i32 + dy, z as i32 + dz];
+                    let size = [w, h, w];
+                    if (0..3).any(|i| next[i] < 0 || next[i] >= size[i] as i32) {
+                        continue;
+                    }
+                    let next = next.map(|c| c as usize);
+                    if visited[index(next)] || Self::is_opaque(blocks, next, base) {
+                        continue;
+                    }
+                    visited[index(next)] = true;
+                    stack.push(next);
+                }
+            }
+            for a in Face::ALL {
+                for b in Face::ALL {
+                    if faces & a.bit() != 0 && faces & b.bit() != 0 {
+                        connectivity.connect(a, b);
+                    }
+                }
+            }
+        }
+        connectivity
+    }
+
+    fn is_opaque(blocks: &ChunkStorage, [x, y, z]: [usize; 3], base: usize) -> bool {
+        blocks.get_local(LocalPos::new(x, base + y, z)).is_opaque()
+    }
+
+    /// Faces of a section a block position inside of it lies on
+    fn touched([x, y, z]: [usize; 3], w: usize, h: usize) -> u8 {
+        let mut faces = 0;
+        let mut on = |face: Face, touches: bool| {
+            if touches {
+                faces |= face.bit();
+            }
+        };
+        on(Face::West, x == 0);
+        on(Face::East, x == w - 1);
+        on(Face::Down, y == 0);
+        on(Face::Up, y == h - 1);
+        on(Face::North, z == 0);
+        on(Face::South, z == w - 1);
+        faces
+    }
+}
+
+impl Default for ChunkVisibility {
+    /// Open, a chunk without a computed graph is never culled
+    fn default() -> Self {
+        Self::open()
+    }
+}
+
+/// Chunks which can be seen through open space from a camera block.
+///
+/// `graph` gives the visibility of a chunk, the traversal does not go through
+/// chunks without one and never further than `distance` chunks from the camera.
+/// A camera above the chunks looks into the top section of every chunk
+pub fn visible_chunks<'a, F>(camera: BlockPos, distance: i32, graph: F) -> HashSet<ChunkPos>
+where
+    F: Fn(ChunkPos) -> Option<&'a ChunkVisibility>,
+{
+    let sections = Chunk::SECTIONS as i32;
+    let center = camera.chunk();
+    // a section and the face it was entered through, with the directions taken so far
+    let mut queue = VecDeque::new();
+    let mut visited = HashSet::new();
+    let mut visible = HashSet::new();
+    if camera.y >= Chunk::HEIGHT as i32 {
+        for dx in -distance..=distance {
+            for dz in -distance..=distance {
+                let pos = center.offset(dx, dz);
+                if graph(pos).is_some() {
+                    visited.insert((pos, sections - 1));
+                    queue.push_back((pos, sections - 1, Some(Face::Up), Face::Down.bit()));
+                }
+            }
+        }
+    } else {
+        let section = camera.y.div_euclid(Chunk::SECTION_HEIGHT as i32);
+        let section = section.clamp(0, sections - 1);
+        visited.insert((center, section));
+        queue.push_back((center, section, None, 0u8));
+    }
+    while let Some((pos, section, entered, directions)) = queue.pop_front() {
+        visible.insert(pos);
+        let connectivity = match graph(pos) {
+            Some(graph) => graph.section(section as usize),
+            None => Connectivity::OPEN,
+        };
+        for face in Face::ALL {
+            if directions & face.opposite().bit() != 0 {
+                continue;
+            }
+            if let Some(entered) = entered {
+                if !connectivity.connects(entered, face) {
+                    continue;
+                }
+            }
+            let [dx, dy, dz] = face.offset();
+            let (next, next_section) = (pos.offset(dx, dz), section + dy);
+            if next_section < 0 || next_section >= sections {
+                continue;
+            }
+            let far = (next.x - center.x).abs().max((next.z - center.z).abs()) > distance;
+            if far || graph(next).is_none() || !visited.insert((next, next_section)) {
+                continue;
+            }
+            queue.push_back((
+                next,
+                next_section,
+                Some(face.opposite()),
+                directions | face.bit(),
+            ));
+        }
+    }
+    visible
+}
@@ -0,0 +1,201 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+use crate::coords::ChunkPos;
+
+use super::lod::Lod;
+use super::mesher::{ChunkMesh, Mesher};
+use super::neighbours::ChunkNeighbours;
+use super::storage::ChunkStorage;
+use super::Chunk;
+
+/// Blocks of a chunk and the 8 chunks around it, indexed by `[dx + 1][dz + 1]`,
+/// copied out of the world so a worker can mesh them
+pub type NeighbourBlocks = Box<[[Option<ChunkStorage>; 3]; 3]>;
+
+/// Cancels a job, it is shared by the sender of the job, the job and its result
+#[derive(Debug, Clone, Default)]
+pub struct JobHandle(Arc<AtomicBool>);
+
+impl JobHandle {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// `true` if both handles belong to the same job
+    pub fn same(&self, other: &JobHandle) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+#[derive(Debug)]
+pub enum JobKind {
+    Generate {
+        seed: u64,
+    },
+    Mesh {
+        blocks: NeighbourBlocks,
+        mesher: Mesher,
+        lod: Lod,
+    },
+}
+
+/// Work for a chunk, it is skipped if it was cancelled before a worker picked it up
+#[derive(Debug)]
+pub struct Job {
+    pub pos: ChunkPos,
+    pub kind: JobKind,
+    pub handle: JobHandle,
+}
+
+#[derive(Debug)]
+pub enum JobOutput {
+    Generated(ChunkStorage),
+    Meshed(Mesher, ChunkMesh),
+    /// The job was cancelled before a worker picked it up
+    Cancelled,
+}
+
+#[derive(Debug)]
+pub struct JobResult {
+    pub pos: ChunkPos,
+    pub output: JobOutput,
+    /// Handle of the job, it may have been cancelled while the job was running
+    pub handle: JobHandle,
+}
+
+/// Threads generating and meshing chunks off the render thread.
+///
+/// Jobs are taken in the order they were sent, results come back through
+/// [`WorkerPool::try_recv`] in the order they were finished. Every job has a result,
+/// [`JobOutput::Cancelled`] for the cancelled ones.
+#[derive(Debug)]
+pub struct WorkerPool {
+    jobs: Option<Sender<Job>>,
+    results: Receiver<JobResult>,
+    /// Set when the pool is dropped, jobs still in the queue are skipped
+    stopped: Arc<AtomicBool>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl WorkerPool {
+    /// A pool with a thread per CPU core, one core is left for rendering
+    pub fn new() -> Self {
+        let threads = thread::available_parallelism()
+            .map(|n| n.get().saturating_sub(1))
+            .unwrap_or(1)
+            .max(1);
+        Self::with_threads(threads)
+    }
+
+    pub fn with_threads(threads: usize) -> Self {
+        let (jobs, job_receiver) = mpsc::channel::<Job>();
+        let (result_sender, results) = mpsc::channel();
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+        let stopped = Arc::new(AtomicBool::new(false));
+        let workers = (0..threads)
+            .map(|i| {
+                let jobs = job_receiver.clone();
+                let results = result_sender.clone();
+                let stopped = stopped.clone();
+                thread::Builder::new()
+                    .name(format!("chunk worker {}", i))
+                    .spawn(move || Self::work(jobs, results, stopped))
+                    .expect("can not spawn a chunk worker")
+            })
+            .collect();
+        log::debug!("{} chunk workers are started", threads);
+        WorkerPool {
+            jobs: Some(jobs),
+            results,
+            stopped,
+            workers,
+        }
+    }
+
+    fn work(jobs: Arc<Mutex<Receiver<Job>>>, results: Sender<JobResult>, stopped: Arc<AtomicBool>) {
+        loop {
+            // the lock is released as soon as a job is received
+            let job = match jobs.lock().unwrap().recv() {
+                Ok(job) => job,
+                // the pool is dropped
+                Err(_) => return,
+            };
+            if stopped.load(Ordering::Relaxed) {
+                return;
+            }
+            let output = if job.handle.is_cancelled() {
+                log::trace!("job for chunk {:?} is cancelled", job.pos);
+                JobOutput::Cancelled
+            } else {
+                Self::run(job.pos, job.kind)
+            };
+            let result = JobResult {
+                pos: job.pos,
+                output,
+                handle: job.handle,
+            };
+            if results.send(result).is_err() {
+                return;
+            }
+        }
+    }
+
+    fn run(pos: ChunkPos, kind: JobKind) -> JobOutput {
+        match kind {
+            JobKind::Generate { seed } => JobOutput::Generated(Chunk::generate(seed, pos)),
+            JobKind::Mesh {
+                blocks,
+                mesher,
+                lod,
+            } => {
+                let mut chunks = [[None; 3]; 3];
+                for (row, blocks) in chunks.iter_mut().zip(blocks.iter()) {
+                    for (chunk, blocks) in row.iter_mut().zip(blocks) {
+                        *chunk = blocks.as_ref();
+                    }
+                }
+                let neighbours = ChunkNeighbours::from_chunks(chunks);
+                JobOutput::Meshed(mesher, mesher.build_at(&neighbours, lod))
+            }
+        }
+    }
+
+    pub fn threads(&self) -> usize {
+        self.workers.len()
+    }
+
+    pub fn send(&self, job: Job) {
+        if let Some(jobs) = &self.jobs {
+            jobs.send(job).expect("chunk workers have stopped");
+        }
+    }
+
+    /// A finished job, `None` if nothing is finished yet
+    pub fn try_recv(&self) -> Option<JobResult> {
+        self.results.try_recv().ok()
+    }
+}
+
+impl Default for WorkerPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for WorkerPool {
+    fn drop(&mut self) {
+        // the workers stop once they finish the current job
+        self.stopped.store(true, Ordering::Relaxed);
+        self.jobs.take();
+        for worker in self.workers.drain(..) {
+            worker.join().ok();
+        }
+    }
+}
@@ -0,0 +1,177 @@
+//! Integer coordinates of blocks and chunks.
+//!
+//! * [`BlockPos`] - a block in the world, any `i32`
+//! * [`ChunkPos`] - a chunk column, the chunk `(x, z)` starts at the block `(x * WIDTH, 0, z * WIDTH)`
+//! * [`LocalPos`] - a block inside of its chunk, `0..WIDTH`, `0..HEIGHT`, `0..WIDTH`
+//!
+//! Negative block coordinates belong to negative chunks, the conversions use floor division,
+//! so the block `-1` is the last block of the chunk `-1` and not the first block of the chunk `0`.
+use std::ops::{Add, Sub};
+
+use crate::chunk::Chunk;
+use crate::Position;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BlockPos {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ChunkPos {
+    pub x: i32,
+    pub z: i32,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LocalPos {
+    pub x: usize,
+    pub y: usize,
+    pub z: usize,
+}
+
+impl BlockPos {
+    pub const fn new(x: i32, y: i32, z: i32) -> Self {
+        BlockPos { x, y, z }
+    }
+
+    /// The block containing a point of the world
+    pub fn containing(position: Position) -> Self {
+        BlockPos::new(
+            position.x.floor() as i32,
+            position.y.floor() as i32,
+            position.z.floor() as i32,
+        )
+    }
+
+    pub fn offset(self, dx: i32, dy: i32, dz: i32) -> Self {
+        BlockPos::new(self.x + dx, self.y + dy, self.z + dz)
+    }
+
+    /// Chunk column of the block, blocks above and below the chunk height belong to it as well
+    pub fn chunk(self) -> ChunkPos {
+        let width = Chunk::WIDTH as i32;
+        ChunkPos::new(self.x.div_euclid(width), self.z.div_euclid(width))
+    }
+
+    /// Position of the block inside of its chunk, `None` if it is above or below the chunk
+    pub fn local(self) -> Option<LocalPos> {
+        let width = Chunk::WIDTH as i32;
+        if self.y < 0 || self.y >= Chunk::HEIGHT as i32 {
+            return None;
+        }
+        Some(LocalPos::new(
+            self.x.rem_euclid(width) as usize,
+            self.y as usize,
+            self.z.rem_euclid(width) as usize,
+        ))
+    }
+
+    /// The chunk and the local position of the block, `None` if it is above or below the chunk
+    pub fn split(self) -> Option<(ChunkPos, LocalPos)> {
+        Some((self.chunk(), self.local()?))
+    }
+
+    /// The corner of the block with the smallest coordinates
+    pub fn position(self) -> Position {
+        Position::new(self.x as f32, self.y as f32, self.z as f32)
+    }
+}
+
+impl Add for BlockPos {
+    type Output = BlockPos;
+    fn add(self, other: BlockPos) -> BlockPos {
+        self.offset(other.x, other.y, other.z)
+    }
+}
+
+impl Sub for BlockPos {
+    type Output = BlockPos;
+    fn sub(self, other: BlockPos) -> BlockPos {
+        self.offset(-other.x, -other.y, -other.z)
+    }
+}
+
+impl From<[i32; 3]> for BlockPos {
+    fn from([x, y, z]: [i32; 3]) -> Self {
+        BlockPos::new(x, y, z)
+    }
+}
+
+impl From<BlockPos> for [i32; 3] {
+    fn from(pos: BlockPos) -> Self {
+        [pos.x, pos.y, pos.z]
+    }
+}
+
+/// A local position as a block position relative to the first block of its chunk
+impl From<LocalPos> for BlockPos {
+    fn from(local: LocalPos) -> Self {
+        BlockPos::new(local.x as i32, local.y as i32, local.z as i32)
+    }
+}
+
+impl ChunkPos {
+    pub const fn new(x: i32, z: i32) -> Self {
+        ChunkPos { x, z }
+    }
+
+    pub fn offset(self, dx: i32, dz: i32) -> Self {
+        ChunkPos::new(self.x + dx, self.z + dz)
+    }
+
+    /// The first block of the chunk
+    pub fn origin(self) -> BlockPos {
+        let width = Chunk::WIDTH as i32;
+        BlockPos::new(self.x * width, 0, self.z * width)
+    }
+
+    /// World position of the block at a local position of the chunk
+    pub fn block(self, local: LocalPos) -> BlockPos {
+        self.origin() + BlockPos::from(local)
+    }
+
+    /// World position of the first block of the chunk
+    pub fn position(self) -> Position {
+        self.origin().position()
+    }
+}
+
+impl From<(i32, i32)> for ChunkPos {
+    fn from((x, z): (i32, i32)) -> Self {
+        ChunkPos::new(x, z)
+    }
+}
+
+impl From<ChunkPos> for (i32, i32) {
+    fn from(pos: ChunkPos) -> Self {
+        (pos.x, pos.z)
+    }
+}
+
+impl LocalPos {
+    pub fn new(x: usize, y: usize, z: usize) -> Self {
+        debug_assert!(x < Chunk::WIDTH && y < Chunk::HEIGHT && z < Chunk::WIDTH);
+        LocalPos { x, y, z }
+    }
+
+    /// Index of the block in the chunk storage, blocks are laid out by `y`, then `z`, then `x`
+    pub fn index(self) -> usize {
+        Chunk::WIDTH * Chunk::WIDTH * self.y + Chunk::WIDTH * self.z + self.x
+    }
+
+    pub fn from_index(index: usize) -> Self {
+        debug_assert!(index < Chunk::WIDTH * Chunk::HEIGHT * Chunk::WIDTH);
+        LocalPos::new(
+            index % Chunk::WIDTH,
+            index / (Chunk::WIDTH * Chunk::WIDTH),
+            (index / Chunk::WIDTH) % Chunk::WIDTH,
+        )
+    }
+
+    /// Every local position of a chunk in index order
+    pub fn all() -> impl Iterator<Item = LocalPos> {
+        (0..Chunk::WIDTH * Chunk::HEIGHT * Chunk::WIDTH).map(LocalPos::from_index)
+    }
+}
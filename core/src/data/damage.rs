@@ -0,0 +1,175 @@
+use crate::coords::BlockPos;
+use crate::texture;
+use crate::utils;
+use crate::world::breaking::Breaking;
+
+use wgpu::util::DeviceExt;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct DamageUniform {
+    /// Lowest corner of the damaged block, `w` is the damage stage
+    pub position: [f32; 4],
+}
+
+/// Cracks drawn over the faces of the block being broken, a texture layer per damage stage
+#[derive(Debug)]
+pub struct Damage {
+    pub pipeline: wgpu::RenderPipeline,
+    pub uniform: DamageUniform,
+    pub buffer: wgpu::Buffer,
+    pub texture: texture::Texture,
+    pub bind_group_layout: wgpu::BindGroupLayout,
+    pub bind_group: wgpu::BindGroup,
+    /// The damaged block and its stage, nothing is drawn without one
+    pub block: Option<(BlockPos, u32)>,
+}
+
+impl Damage {
+    /// Textures of the damage stages in `res/textures/blocks`
+    pub const TEXTURES: [&'static str; Breaking::STAGES as usize] = [
+        "anvil_top_damaged_0",
+        "anvil_top_damaged_1",
+        "anvil_top_damaged_2",
+    ];
+    /// Vertices of the 6 faces, the shader builds them from the vertex index
+    pub const VERTICES: u32 = 36;
+
+    pub fn new(engine: &utils::Engine, camera_bind_group_layout: &wgpu::BindGroupLayout) -> Damage {
+        let uniform = DamageUniform::default();
+        let buffer = engine
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Damage Buffer"),
+                contents: bytemuck::cast_slice(&[uniform]),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            });
+        let texture = {
+            let out_dir = std::env::var("OUT_DIR").unwrap();
+            let root_path = std::path::Path::new(&out_dir)
+                .join("res")
+                .join("textures")
+                .join("blocks");
+            let images: Vec<_> = Self::TEXTURES
+                .iter()
+                .map(|name| image::open(root_path.join(format!("{}.png", name))).unwrap())
+                .collect();
+            // not sRGB, the shader compares the grey values as they are stored
+            texture::Texture::from_images(
+                &engine.device,
+                &engine.queue,
+                &images,
+                Some("Damage array"),
+                true,
+            )
+            .unwrap()
+        };
+        let bind_group_layout =
+            engine
+                .device
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: Some("damage_bind_group_layout"),
+                    entries: &[
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 0,
+                            visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                            ty: wgpu::BindingType::Buffer {
+                                ty: wgpu::BufferBindingType::Uniform,
+                                has_dynamic_offset: false,
+                                min_binding_size: None,
+                            },
+                            count: None,
+                        },
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 1,
+                            visibility: wgpu::ShaderStages::FRAGMENT,
+                            ty: wgpu::BindingType::Texture {
+                                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                                multisampled: false,
+                                view_dimension: wgpu::TextureViewDimension::D2Array,
+                            },
+                            count: None,
+                        },
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 2,
+                            visibility: wgpu::ShaderStages::FRAGMENT,
+                            ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                            count: None,
+                        },
+                    ],
+                });
+        let bind_group = engine.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&texture.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&texture.sampler),
+                },
+            ],
+            label: Some("damage_bind_group"),
+        });
+        let layout = engine
+            .device
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Damage Pipeline Layout"),
+                bind_group_layouts: &[camera_bind_group_layout, &bind_group_layout],
+                push_constant_ranges: &[],
+            });
+        let shader = wgpu::ShaderModuleDescriptor {
+            label: Some("Damage Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/damage.wgsl").into()),
+        };
+        // the decal is multiplied with the block below it
+        let multiply = wgpu::BlendState {
+            color: wgpu::BlendComponent {
+                src_factor: wgpu::BlendFactor::Dst,
+                dst_factor: wgpu::BlendFactor::Zero,
+                operation: wgpu::BlendOperation::Add,
+            },
+            alpha: wgpu::BlendComponent {
+                src_factor: wgpu::BlendFactor::Zero,
+                dst_factor: wgpu::BlendFactor::One,
+                operation: wgpu::BlendOperation::Add,
+            },
+        };
+        let pipeline = utils::create_overlay_pipeline(
+            &engine.device,
+            &layout,
+            engine.config.format,
+            Some(texture::Texture::DEPTH_FORMAT),
+            shader,
+            wgpu::PrimitiveTopology::TriangleList,
+            multiply,
+            Some("Damage Render Pipeline"),
+        );
+        Damage {
+            pipeline,
+            uniform,
+            buffer,
+            texture,
+            bind_group_layout,
+            bind_group,
+            block: None,
+        }
+    }
+
+    /// Moves the cracks to a block and its damage stage, `None` hides them
+    pub fn update(&mut self, queue: &wgpu::Queue, block: Option<(BlockPos, u32)>) {
+        if block == self.block {
+            return;
+        }
+        self.block = block;
+        if let Some((pos, stage)) = block {
+            self.uniform.position = [pos.x as f32, pos.y as f32, pos.z as f32, stage as f32];
+            queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[self.uniform]));
+        }
+    }
+}
@@ -1,4 +1,7 @@
 pub mod camera;
+pub mod damage;
 pub mod light;
+pub mod outline;
 pub mod shadow;
 pub mod sky;
+pub mod water;
@@ -0,0 +1,113 @@
+use crate::coords::BlockPos;
+use crate::texture;
+use crate::utils;
+
+use wgpu::util::DeviceExt;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct OutlineUniform {
+    /// Lowest corner of the outlined block, `w` is unused
+    pub position: [f32; 4],
+    pub color: [f32; 4],
+}
+
+/// Wireframe box around the block the camera targets
+#[derive(Debug)]
+pub struct Outline {
+    pub pipeline: wgpu::RenderPipeline,
+    pub uniform: OutlineUniform,
+    pub buffer: wgpu::Buffer,
+    pub bind_group_layout: wgpu::BindGroupLayout,
+    pub bind_group: wgpu::BindGroup,
+    /// The outlined block, nothing is drawn without one
+    pub block: Option<BlockPos>,
+}
+
+impl Outline {
+    pub const COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.6];
+    /// Vertices of the 12 edges, the shader builds them from the vertex index
+    pub const VERTICES: u32 = 24;
+
+    pub fn new(
+        engine: &utils::Engine,
+        camera_bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> Outline {
+        let uniform = OutlineUniform {
+            position: [0.0; 4],
+            color: Self::COLOR,
+        };
+        let buffer = engine
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Outline Buffer"),
+                contents: bytemuck::cast_slice(&[uniform]),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            });
+        let bind_group_layout =
+            engine
+                .device
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: Some("outline_bind_group_layout"),
+                    entries: &[wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    }],
+                });
+        let bind_group = engine.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+            label: Some("outline_bind_group"),
+        });
+        let layout = engine
+            .device
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Outline Pipeline Layout"),
+                bind_group_layouts: &[camera_bind_group_layout, &bind_group_layout],
+                push_constant_ranges: &[],
+            });
+        let shader = wgpu::ShaderModuleDescriptor {
+            label: Some("Outline Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/outline.wgsl").into()),
+        };
+        let pipeline = utils::create_overlay_pipeline(
+            &engine.device,
+            &layout,
+            engine.config.format,
+            Some(texture::Texture::DEPTH_FORMAT),
+            shader,
+            wgpu::PrimitiveTopology::LineList,
+            wgpu::BlendState::ALPHA_BLENDING,
+            Some("Outline Render Pipeline"),
+        );
+        Outline {
+            pipeline,
+            uniform,
+            buffer,
+            bind_group_layout,
+            bind_group,
+            block: None,
+        }
+    }
+
+    /// Moves the box to `block`, `None` hides it
+    pub fn update(&mut self, queue: &wgpu::Queue, block: Option<BlockPos>) {
+        if block == self.block {
+            return;
+        }
+        self.block = block;
+        if let Some(pos) = block {
+            self.uniform.position = [pos.x as f32, pos.y as f32, pos.z as f32, 0.0];
+            queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[self.uniform]));
+        }
+    }
+}
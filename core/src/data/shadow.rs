@@ -1,9 +1,10 @@
 use super::light;
 use crate::{
     instance,
-    model::{self, Vertex},
+    model::Vertex,
     texture,
     utils::Engine,
+    vertex::ChunkVertex,
 };
 #[derive(Debug)]
 pub struct Shadow {
@@ -41,7 +42,7 @@ impl Shadow {
                 &engine.device,
                 &layout,
                 Some(texture::Texture::DEPTH_FORMAT),
-                &[model::ModelVertex::desc(), instance::InstanceRaw::desc()],
+                &[ChunkVertex::desc(), instance::InstanceRaw::desc()],
                 shader,
             )
         };
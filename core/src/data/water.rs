@@ -0,0 +1,165 @@
+use crate::block::{registry, RenderType};
+use crate::coords::BlockPos;
+use crate::data::sky;
+use crate::instance;
+use crate::model::Vertex;
+use crate::texture;
+use crate::utils;
+use crate::vertex::ChunkVertex;
+use crate::world::World;
+use crate::Position;
+
+use wgpu::util::DeviceExt;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct WaterUniform {
+    /// Seconds since the start, moves the waves
+    pub time: f32,
+    _padding: [f32; 3],
+}
+
+/// Pipeline of the [`RenderType::Water`] chunk geometry. The surface reflects the sky cubemap,
+/// its bind group holds the cubemap next to the uniform
+#[derive(Debug)]
+pub struct Water {
+    pub pipeline: wgpu::RenderPipeline,
+    pub uniform: WaterUniform,
+    pub buffer: wgpu::Buffer,
+    pub bind_group_layout: wgpu::BindGroupLayout,
+    pub bind_group: wgpu::BindGroup,
+}
+
+impl Water {
+    /// Color of the fog around a camera under water
+    pub const FOG_COLOR: [f32; 3] = [0.05, 0.18, 0.35];
+    /// The fog hides most of what is further than about 20 blocks
+    pub const FOG_DENSITY: f32 = 0.12;
+
+    pub fn new(
+        engine: &utils::Engine,
+        texture_bind_group_layout: &wgpu::BindGroupLayout,
+        camera_bind_group_layout: &wgpu::BindGroupLayout,
+        light_bind_group_layout: &wgpu::BindGroupLayout,
+        sky: &sky::Sky,
+    ) -> Water {
+        let uniform = WaterUniform::default();
+        let buffer = engine
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Water Buffer"),
+                contents: bytemuck::cast_slice(&[uniform]),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            });
+        let bind_group_layout =
+            engine
+                .device
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: Some("water_bind_group_layout"),
+                    entries: &[
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 0,
+                            visibility: wgpu::ShaderStages::FRAGMENT,
+                            ty: wgpu::BindingType::Buffer {
+                                ty: wgpu::BufferBindingType::Uniform,
+                                has_dynamic_offset: false,
+                                min_binding_size: None,
+                            },
+                            count: None,
+                        },
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 1,
+                            visibility: wgpu::ShaderStages::FRAGMENT,
+                            ty: wgpu::BindingType::Texture {
+                                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                                multisampled: false,
+                                view_dimension: wgpu::TextureViewDimension::Cube,
+                            },
+                            count: None,
+                        },
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 2,
+                            visibility: wgpu::ShaderStages::FRAGMENT,
+                            ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                            count: None,
+                        },
+                    ],
+                });
+        let bind_group = engine.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&sky.texture.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&sky.texture.sampler),
+                },
+            ],
+            label: Some("water_bind_group"),
+        });
+        let layout = engine
+            .device
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Water Pipeline Layout"),
+                bind_group_layouts: &[
+                    texture_bind_group_layout,
+                    camera_bind_group_layout,
+                    light_bind_group_layout,
+                    &bind_group_layout,
+                ],
+                push_constant_ranges: &[],
+            });
+        let shader = engine
+            .device
+            .create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("Water Shader"),
+                source: wgpu::ShaderSource::Wgsl(
+                    ChunkVertex::shader_source(include_str!("../shaders/water.wgsl")).into(),
+                ),
+            });
+        let pipeline = utils::create_chunk_pipeline(
+            &engine.device,
+            &layout,
+            engine.config.format,
+            Some(texture::Texture::DEPTH_FORMAT),
+            &[ChunkVertex::desc(), instance::InstanceRaw::desc()],
+            &shader,
+            RenderType::Water,
+        );
+        Water {
+            pipeline,
+            uniform,
+            buffer,
+            bind_group_layout,
+            bind_group,
+        }
+    }
+
+    /// Moves the waves on by `dt`
+    pub fn update(&mut self, queue: &wgpu::Queue, dt: std::time::Duration) {
+        self.uniform.time += dt.as_secs_f32();
+        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[self.uniform]));
+    }
+
+    /// Whether `eye` is inside a water block and below its lowered surface
+    pub fn is_underwater(world: &World, eye: Position) -> bool {
+        let is_water = |pos: BlockPos| {
+            world
+                .get_block(pos)
+                .is_some_and(|kind| registry().render_type(kind) == RenderType::Water)
+        };
+        let pos = BlockPos::containing(eye);
+        if !is_water(pos) {
+            return false;
+        }
+        // the top of a surface block is dry
+        is_water(pos + BlockPos::new(0, 1, 0))
+            || eye.y - (pos.y as f32) < 1.0 - ChunkVertex::LIQUID_DROP
+    }
+}
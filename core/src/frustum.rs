@@ -0,0 +1,78 @@
+use cgmath::{Matrix, Matrix4, Vector3, Vector4};
+
+/// Axis aligned bounding box in world space
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Vector3<f32>,
+    pub max: Vector3<f32>,
+}
+
+impl Aabb {
+    pub fn new(min: Vector3<f32>, max: Vector3<f32>) -> Self {
+        Aabb { min, max }
+    }
+
+    /// The smallest box around the points, `None` if there are no points
+    pub fn around<I>(points: I) -> Option<Self>
+    where
+        I: IntoIterator<Item = Vector3<f32>>,
+    {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        Some(points.fold(Aabb::new(first, first), |aabb, p| Aabb {
+            min: Vector3::new(
+                aabb.min.x.min(p.x),
+                aabb.min.y.min(p.y),
+                aabb.min.z.min(p.z),
+            ),
+            max: Vector3::new(
+                aabb.max.x.max(p.x),
+                aabb.max.y.max(p.y),
+                aabb.max.z.max(p.z),
+            ),
+        }))
+    }
+
+    pub fn translate(&self, offset: Vector3<f32>) -> Self {
+        Aabb::new(self.min + offset, self.max + offset)
+    }
+
+    pub fn center(&self) -> Vector3<f32> {
+        (self.min + self.max) / 2.0
+    }
+}
+
+/// The six planes of a view volume, a point `p` is inside if `plane · (p, 1) >= 0`
+/// for every plane. Works for perspective and orthographic projections alike
+#[derive(Debug, Clone, Copy)]
+pub struct Frustum {
+    /// Left, right, bottom, top, near and far planes
+    planes: [Vector4<f32>; 6],
+}
+
+impl Frustum {
+    /// Planes of a view-projection matrix with wgpu clip space, `0 <= z <= w`
+    pub fn from_matrix(view_proj: Matrix4<f32>) -> Self {
+        let m = view_proj.transpose();
+        let (x, y, z, w) = (m.x, m.y, m.z, m.w);
+        Frustum {
+            planes: [w + x, w - x, w + y, w - y, z, w - z],
+        }
+    }
+
+    /// `false` only if the box is entirely outside of one of the planes,
+    /// a few boxes near the corners of the frustum pass the test while invisible
+    pub fn intersects(&self, aabb: &Aabb) -> bool {
+        let furthest = |n: f32, min: f32, max: f32| if n >= 0.0 { max } else { min };
+        self.planes.iter().all(|plane| {
+            // the corner of the box furthest along the plane normal
+            let corner = Vector4::new(
+                furthest(plane.x, aabb.min.x, aabb.max.x),
+                furthest(plane.y, aabb.min.y, aabb.max.y),
+                furthest(plane.z, aabb.min.z, aabb.max.z),
+                1.0,
+            );
+            cgmath::dot(*plane, corner) >= 0.0
+        })
+    }
+}
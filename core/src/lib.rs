@@ -14,7 +14,9 @@ extern crate lazy_static;
 #[allow(dead_code)]
 pub mod camera;
 pub mod chunk;
+pub mod coords;
 pub mod data;
+pub mod frustum;
 pub mod instance;
 pub mod light;
 #[allow(dead_code)]
@@ -117,9 +119,7 @@ pub async fn run() {
             Event::DeviceEvent {
                 event: DeviceEvent::MouseMotion{ delta, },
                 .. // We're not using device_id currently
-            } => if state.mouse_pressed {
-                state.camera.controller.process_mouse(delta.0, delta.1)
-            }
+            } => state.mouse_motion(delta.0, delta.1),
             Event::WindowEvent {
                 ref event,
                 window_id,
@@ -171,6 +171,8 @@ pub struct CameraUniform {
     view_proj: [[f32; 4]; 4],
     view: [[f32; 4]; 4],
     proj_inv: [[f32; 4]; 4],
+    /// Fog color and density, the density is 0 unless the camera is underwater
+    fog: [f32; 4],
 }
 
 impl CameraUniform {
@@ -180,9 +182,15 @@ impl CameraUniform {
             view_proj: cgmath::Matrix4::identity().into(),
             view: cgmath::Matrix4::identity().into(),
             proj_inv: cgmath::Matrix4::identity().into(),
+            fog: [0.0; 4],
         }
     }
 
+    /// Fogs the view with `color`, a density of 0 turns the fog off
+    pub fn set_fog(&mut self, color: [f32; 3], density: f32) {
+        self.fog = [color[0], color[1], color[2], density];
+    }
+
     // UPDATED!
     pub fn update_view_proj(&mut self, camera: &camera::Camera, projection: &camera::Projection) {
         self.view_position = camera.position.to_homogeneous().into();
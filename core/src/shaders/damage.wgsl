@@ -0,0 +1,71 @@
+struct Camera {
+    view_pos: vec4<f32>,
+    view_proj: mat4x4<f32>,
+    view: mat4x4<f32>,
+    proj_inv: mat4x4<f32>,
+    fog: vec4<f32>,
+}
+@group(0) @binding(0)
+var<uniform> camera: Camera;
+
+struct Damage {
+    // lowest corner of the damaged block, w is the damage stage
+    position: vec4<f32>,
+}
+@group(1) @binding(0)
+var<uniform> damage: Damage;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    // two triangles for each of the 6 faces, a face is on the low or high side of an axis
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 1.0),
+    );
+    let face = vertex_index / 6u;
+    let corner = corners[vertex_index % 6u];
+    let side = f32(face % 2u);
+    var unit: vec3<f32>;
+    switch (face / 2u) {
+        case 0u: {
+            unit = vec3<f32>(side, corner.x, corner.y);
+        }
+        case 1u: {
+            unit = vec3<f32>(corner.x, side, corner.y);
+        }
+        default: {
+            unit = vec3<f32>(corner.x, corner.y, side);
+        }
+    }
+    // a little larger than the block so the decal is not hidden by its faces
+    let position = damage.position.xyz - vec3<f32>(0.002) + unit * 1.004;
+    var out: VertexOutput;
+    out.clip_position = camera.view_proj * vec4<f32>(position, 1.0);
+    out.tex_coords = corner;
+    return out;
+}
+
+@group(1) @binding(1)
+var t_damage: texture_2d_array<f32>;
+@group(1) @binding(2)
+var s_damage: sampler;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // the anvil textures are a grey strip in the middle with darker dents
+    let uv = vec2<f32>(0.1875 + in.tex_coords.x * 0.625, in.tex_coords.y);
+    let texel = textureSample(t_damage, s_damage, uv, i32(damage.position.w));
+    let grey = dot(texel.rgb, vec3<f32>(0.3, 0.59, 0.11));
+    // multiplied with the block, the grey leaves it as it is and the dents darken it
+    let shade = pow(clamp(grey / 0.22, 0.0, 1.0), 3.0);
+    return vec4<f32>(vec3<f32>(shade), 1.0);
+}
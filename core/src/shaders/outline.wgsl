@@ -0,0 +1,37 @@
+struct Camera {
+    view_pos: vec4<f32>,
+    view_proj: mat4x4<f32>,
+    view: mat4x4<f32>,
+    proj_inv: mat4x4<f32>,
+    fog: vec4<f32>,
+}
+@group(0) @binding(0)
+var<uniform> camera: Camera;
+
+struct Outline {
+    // lowest corner of the outlined block
+    position: vec4<f32>,
+    color: vec4<f32>,
+}
+@group(1) @binding(0)
+var<uniform> outline: Outline;
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> @builtin(position) vec4<f32> {
+    // the 12 edges of a cube as pairs of corners, a corner is x | y << 1 | z << 2
+    var corners = array<u32, 24>(
+        0u, 1u, 2u, 3u, 4u, 5u, 6u, 7u,
+        0u, 2u, 1u, 3u, 4u, 6u, 5u, 7u,
+        0u, 4u, 1u, 5u, 2u, 6u, 3u, 7u,
+    );
+    let corner = corners[vertex_index];
+    let unit = vec3<f32>(f32(corner & 1u), f32((corner >> 1u) & 1u), f32((corner >> 2u) & 1u));
+    // a little larger than the block so its faces do not hide the lines
+    let position = outline.position.xyz - vec3<f32>(0.002) + unit * 1.004;
+    return camera.view_proj * vec4<f32>(position, 1.0);
+}
+
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    return outline.color;
+}
@@ -3,6 +3,10 @@
 struct Camera {
     view_pos: vec4<f32>,
     view_proj: mat4x4<f32>,
+    view: mat4x4<f32>,
+    proj_inv: mat4x4<f32>,
+    // rgb color and density of the underwater fog, the density is 0 above water
+    fog: vec4<f32>,
 }
 @group(1) @binding(0)
 var<uniform> camera: Camera;
@@ -16,12 +20,63 @@ struct Light {
 @group(2) @binding(0)
 var<uniform> light: Light;
 
+// Packed chunk vertex, see `vertex::ChunkVertex`
 struct VertexInput {
-    @location(0) position: vec3<f32>,
-    @location(1) tex_coords: vec2<f32>,
-    @location(2) normal: vec3<f32>,
-    @location(3) tangent: vec3<f32>,
-    @location(4) bitangent: vec3<f32>,
+    @location(0) data: vec2<u32>,
+}
+struct ChunkVertex {
+    position: vec3<f32>,
+    tex_coords: vec2<f32>,
+    normal: vec3<f32>,
+    tangent: vec3<f32>,
+    bitangent: vec3<f32>,
+    // 0.0 is a fully occluded corner, 1.0 is an open one
+    ao: f32,
+    // layer of the block texture arrays
+    material: u32,
+}
+fn decode_vertex(data: vec2<u32>) -> ChunkVertex {
+    // faces in `Voxel::FACES` order: back, front, top, bottom, left, right
+    var normals = array<vec3<f32>, 6>(
+        vec3<f32>(0.0, 0.0, -1.0),
+        vec3<f32>(0.0, 0.0, 1.0),
+        vec3<f32>(0.0, 1.0, 0.0),
+        vec3<f32>(0.0, -1.0, 0.0),
+        vec3<f32>(-1.0, 0.0, 0.0),
+        vec3<f32>(1.0, 0.0, 0.0),
+    );
+    var tangents = array<vec3<f32>, 6>(
+        vec3<f32>(1.0, 0.0, 0.0),
+        vec3<f32>(-1.0, 0.0, 0.0),
+        vec3<f32>(-1.0, 0.0, 0.0),
+        vec3<f32>(-1.0, 0.0, 0.0),
+        vec3<f32>(0.0, 0.0, -1.0),
+        vec3<f32>(0.0, 0.0, 1.0),
+    );
+    var bitangents = array<vec3<f32>, 6>(
+        vec3<f32>(0.0, 1.0, 0.0),
+        vec3<f32>(0.0, 1.0, 0.0),
+        vec3<f32>(0.0, 0.0, -1.0),
+        vec3<f32>(0.0, 0.0, 1.0),
+        vec3<f32>(0.0, 1.0, 0.0),
+        vec3<f32>(0.0, 1.0, 0.0),
+    );
+    let face = (data.x >> 17u) & 7u;
+    var out: ChunkVertex;
+    out.position = vec3<f32>(
+        f32(data.x & 31u),
+        f32((data.x >> 5u) & 127u),
+        f32((data.x >> 12u) & 31u),
+    );
+    out.tex_coords = vec2<f32>(f32(data.y & 127u), f32((data.y >> 7u) & 127u));
+    out.normal = normals[face];
+    out.tangent = tangents[face];
+    out.bitangent = bitangents[face];
+    out.ao = f32((data.x >> 20u) & 3u) / 3.0;
+    // top corners of a liquid surface, `LIQUID_DROP` is declared by `ChunkVertex::shader_source`
+    out.position.y = out.position.y - f32((data.x >> 22u) & 1u) * LIQUID_DROP;
+    out.material = data.y >> 16u;
+    return out;
 }
 struct InstanceInput {
     @location(5) model_matrix_0: vec4<f32>,
@@ -41,13 +96,16 @@ struct VertexOutput {
     @location(3) tangent_view_position: vec3<f32>,
     @location(4) world_position: vec4<f32>,
     @location(5) shadow_pos: vec3<f32>,
+    @location(6) ao: f32,
+    @location(7) @interpolate(flat) material: u32,
 }
 
 @vertex
 fn vs_main(
-    model: VertexInput,
+    vertex: VertexInput,
     instance: InstanceInput,
 ) -> VertexOutput {
+    let model = decode_vertex(vertex.data);
     let model_matrix = mat4x4<f32>(
         instance.model_matrix_0,
         instance.model_matrix_1,
@@ -76,6 +134,8 @@ fn vs_main(
     out.world_position = world_position;
     out.clip_position = camera.view_proj * world_position;
     out.tex_coords = model.tex_coords;
+    out.material = model.material;
+    out.ao = model.ao;
     out.tangent_position = tangent_matrix * world_position.xyz;
     out.tangent_view_position = tangent_matrix * camera.view_pos.xyz;
     out.tangent_light_position = tangent_matrix * light.view_pos.xyz;
@@ -86,15 +146,15 @@ fn vs_main(
 
 // Fragment shader
 @group(0) @binding(0)
-var t_diffuse: texture_2d<f32>;
+var t_diffuse: texture_2d_array<f32>;
 @group(0)@binding(1)
 var s_diffuse: sampler;
 @group(0)@binding(2)
-var t_normal: texture_2d<f32>;
+var t_normal: texture_2d_array<f32>;
 @group(0) @binding(3)
 var s_normal: sampler;
 @group(0)@binding(4)
-var t_specular: texture_2d<f32>;
+var t_specular: texture_2d_array<f32>;
 @group(0) @binding(5)
 var s_specular: sampler;
 
@@ -140,11 +200,10 @@ fn fetch_shadow(homogeneous_coords: vec4<f32>) -> f32 {
 //     return shadow;
 // }
 
-@fragment
-fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    let object_color: vec4<f32> = textureSample(t_diffuse, s_diffuse, in.tex_coords);
-    let object_normal: vec4<f32> = textureSample(t_normal, s_normal, in.tex_coords);
-    let object_specular: vec4<f32> = textureSample(t_specular, s_specular, in.tex_coords);
+// Lights a fragment of a block, `object_color` is its diffuse texel
+fn shade(in: VertexOutput, object_color: vec4<f32>) -> vec4<f32> {
+    let object_normal: vec4<f32> = textureSample(t_normal, s_normal, in.tex_coords, i32(in.material));
+    let object_specular: vec4<f32> = textureSample(t_specular, s_specular, in.tex_coords, i32(in.material));
 
 
     let distance_from_light = distance(in.tangent_position, in.tangent_light_position);
@@ -169,7 +228,28 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
 
     let shadow = fetch_shadow(light.view_proj * in.world_position);
 
-    let result = (ambient_color + diffuse_color) * object_color.xyz * min(ambient_strength + shadow, 1.0) + specular_color * object_specular.xyz * shadow;
+    // ambient occlusion darkens corners and creases, fully occluded corners keep some light
+    let ao = mix(0.3, 1.0, in.ao);
 
-    return vec4<f32>(result, object_color.a);
-} 
\ No newline at end of file
+    let result = ((ambient_color + diffuse_color) * object_color.xyz * min(ambient_strength + shadow, 1.0) + specular_color * object_specular.xyz * shadow) * ao;
+
+    // the deeper the block is in the water around the camera, the more it takes the water color
+    let fog = 1.0 - exp(-camera.fog.a * distance(in.world_position.xyz, camera.view_pos.xyz));
+    return vec4<f32>(mix(result, camera.fog.rgb, fog), object_color.a);
+} 
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let object_color: vec4<f32> = textureSample(t_diffuse, s_diffuse, in.tex_coords, i32(in.material));
+    return shade(in, object_color);
+}
+
+// Alpha tested blocks such as glass and leaves, transparent texels are cut out
+@fragment
+fn fs_cutout(in: VertexOutput) -> @location(0) vec4<f32> {
+    let object_color: vec4<f32> = textureSample(t_diffuse, s_diffuse, in.tex_coords, i32(in.material));
+    if (object_color.a < 0.5) {
+        discard;
+    }
+    return shade(in, vec4<f32>(object_color.rgb, 1.0));
+}
@@ -10,9 +10,17 @@ struct Light {
 @group(0) @binding(0)
 var<uniform> light: Light;
 
+// Packed chunk vertex, see `vertex::ChunkVertex`, only the position is needed
 struct VertexInput {
-    @location(0) position: vec3<f32>,
+    @location(0) data: vec2<u32>,
 };
+fn decode_position(data: vec2<u32>) -> vec3<f32> {
+    return vec3<f32>(
+        f32(data.x & 31u),
+        f32((data.x >> 5u) & 127u),
+        f32((data.x >> 12u) & 31u),
+    );
+}
 struct InstanceInput {
     @location(5) model_matrix_0: vec4<f32>,
     @location(6) model_matrix_1: vec4<f32>,
@@ -35,7 +43,7 @@ fn vs_main(model: VertexInput, instance: InstanceInput) -> VertexOutput {
         instance.model_matrix_3,
     );
 
-    let world_position = model_matrix * vec4<f32>(model.position, 1.0);
+    let world_position = model_matrix * vec4<f32>(decode_position(model.data), 1.0);
     let clip_position = light.view_proj * world_position;
     var out: VertexOutput;
     out.clip_position = clip_position;
@@ -9,6 +9,7 @@ struct Camera {
     view_proj: mat4x4<f32>,
     view: mat4x4<f32>,
     proj_inv: mat4x4<f32>,
+    fog: vec4<f32>,
 }
 @group(0) @binding(0)
 var<uniform> camera: Camera;
@@ -42,5 +43,9 @@ var r_sampler: sampler;
 @fragment
 fn fs_main(in: SkyOutput) -> @location(0) vec4<f32> {
     // return textureSample(r_texture, r_sampler, in.position.xyz);
+    // the sky is out of sight under water
+    if (camera.fog.a > 0.0) {
+        return vec4<f32>(camera.fog.rgb, 1.0);
+    }
     return textureSample(r_texture, r_sampler, in.uv);
 }
\ No newline at end of file
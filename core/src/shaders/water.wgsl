@@ -0,0 +1,142 @@
+// Vertex shader
+
+struct Camera {
+    view_pos: vec4<f32>,
+    view_proj: mat4x4<f32>,
+    view: mat4x4<f32>,
+    proj_inv: mat4x4<f32>,
+    // rgb color and density of the underwater fog, the density is 0 above water
+    fog: vec4<f32>,
+}
+@group(1) @binding(0)
+var<uniform> camera: Camera;
+
+struct Light {
+    view_pos: vec4<f32>,
+    view_proj: mat4x4<f32>,
+    color: vec3<f32>,
+    strength: f32,
+}
+@group(2) @binding(0)
+var<uniform> light: Light;
+
+struct Water {
+    // seconds since the start, moves the waves
+    time: f32,
+}
+@group(3) @binding(0)
+var<uniform> water: Water;
+
+// Packed chunk vertex, see `vertex::ChunkVertex`
+struct VertexInput {
+    @location(0) data: vec2<u32>,
+}
+struct InstanceInput {
+    @location(5) model_matrix_0: vec4<f32>,
+    @location(6) model_matrix_1: vec4<f32>,
+    @location(7) model_matrix_2: vec4<f32>,
+    @location(8) model_matrix_3: vec4<f32>,
+    @location(9) normal_matrix_0: vec3<f32>,
+    @location(10) normal_matrix_1: vec3<f32>,
+    @location(11) normal_matrix_2: vec3<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+    @location(1) world_position: vec3<f32>,
+    @location(2) world_normal: vec3<f32>,
+    @location(3) @interpolate(flat) material: u32,
+}
+
+@vertex
+fn vs_main(
+    vertex: VertexInput,
+    instance: InstanceInput,
+) -> VertexOutput {
+    // faces in `Voxel::FACES` order: back, front, top, bottom, left, right
+    var normals = array<vec3<f32>, 6>(
+        vec3<f32>(0.0, 0.0, -1.0),
+        vec3<f32>(0.0, 0.0, 1.0),
+        vec3<f32>(0.0, 1.0, 0.0),
+        vec3<f32>(0.0, -1.0, 0.0),
+        vec3<f32>(-1.0, 0.0, 0.0),
+        vec3<f32>(1.0, 0.0, 0.0),
+    );
+    let data = vertex.data;
+    let position = vec3<f32>(
+        f32(data.x & 31u),
+        // top corners of a liquid surface, `LIQUID_DROP` is declared by `ChunkVertex::shader_source`
+        f32((data.x >> 5u) & 127u) - f32((data.x >> 22u) & 1u) * LIQUID_DROP,
+        f32((data.x >> 12u) & 31u),
+    );
+    let model_matrix = mat4x4<f32>(
+        instance.model_matrix_0,
+        instance.model_matrix_1,
+        instance.model_matrix_2,
+        instance.model_matrix_3,
+    );
+    let normal_matrix = mat3x3<f32>(
+        instance.normal_matrix_0,
+        instance.normal_matrix_1,
+        instance.normal_matrix_2,
+    );
+    let world_position = model_matrix * vec4<f32>(position, 1.0);
+    var out: VertexOutput;
+    out.clip_position = camera.view_proj * world_position;
+    out.tex_coords = vec2<f32>(f32(data.y & 127u), f32((data.y >> 7u) & 127u));
+    out.world_position = world_position.xyz;
+    out.world_normal = normalize(normal_matrix * normals[(data.x >> 17u) & 7u]);
+    out.material = data.y >> 16u;
+    return out;
+}
+
+
+// Fragment shader
+@group(0) @binding(0)
+var t_diffuse: texture_2d_array<f32>;
+@group(0) @binding(1)
+var s_diffuse: sampler;
+
+@group(3) @binding(1)
+var t_sky: texture_cube<f32>;
+@group(3) @binding(2)
+var s_sky: sampler;
+
+// Slope of the surface along x and z, a few sine waves running in different directions
+fn wave_slope(p: vec2<f32>, time: f32) -> vec2<f32> {
+    let a = cos(dot(p, vec2<f32>(1.3, 0.4)) + time * 1.7) * 0.06;
+    let b = cos(dot(p, vec2<f32>(-0.5, 1.1)) + time * 1.3) * 0.05;
+    let c = cos(dot(p, vec2<f32>(2.3, 2.1)) + time * 2.9) * 0.02;
+    return a * vec2<f32>(1.3, 0.4) + b * vec2<f32>(-0.5, 1.1) + c * vec2<f32>(2.3, 2.1);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let object_color = textureSample(t_diffuse, s_diffuse, in.tex_coords, i32(in.material));
+
+    // waves ripple the upward facing surfaces only
+    let slope = wave_slope(in.world_position.xz, water.time) * max(in.world_normal.y, 0.0);
+    var normal = normalize(in.world_normal - vec3<f32>(slope.x, 0.0, slope.y));
+    let view_dir = normalize(camera.view_pos.xyz - in.world_position);
+    // seen from below the surface faces the camera the other way
+    if (dot(view_dir, normal) < 0.0) {
+        normal = -normal;
+    }
+
+    // Schlick's approximation, water reflects 2% of the light head on
+    let cos_theta = clamp(dot(view_dir, normal), 0.0, 1.0);
+    let fresnel = 0.02 + 0.98 * pow(1.0 - cos_theta, 5.0);
+    let reflection = textureSample(t_sky, s_sky, reflect(-view_dir, normal)).rgb;
+
+    let light_dir = normalize(light.view_pos.xyz - in.world_position);
+    let half_dir = normalize(view_dir + light_dir);
+    let diffuse_strength = 0.5 + 0.5 * clamp(dot(normal, light_dir), 0.0, 1.0) * light.strength;
+    let specular_strength = pow(max(dot(normal, half_dir), 0.0), 64.0) * light.strength;
+    let surface = object_color.rgb * light.color * diffuse_strength;
+    let result = mix(surface, reflection, fresnel) + light.color * specular_strength;
+
+    let fog = 1.0 - exp(-camera.fog.a * distance(in.world_position, camera.view_pos.xyz));
+    let alpha = mix(object_color.a, 1.0, fresnel);
+    return vec4<f32>(mix(result, camera.fog.rgb, fog), mix(alpha, 1.0, fog));
+}
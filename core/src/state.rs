@@ -1,36 +1,87 @@
-use crate::data::{camera, light, shadow, sky};
+use crate::data::{camera, damage, light, outline, shadow, sky, water};
 
-use crate::chunk::Chunk;
+use crate::block::{BlockKind, RenderType};
+use crate::chunk::manager::{ChunkManager, LoadTicket};
+use crate::chunk::mesher::Mesher;
+use crate::chunk::storage::ChunkStorage;
+use crate::coords::{BlockPos, ChunkPos};
+use crate::frustum::Frustum;
 use crate::instance;
 use crate::model;
 use crate::model::DrawLight;
-use crate::model::DrawModel;
 use crate::model::Vertex;
 use crate::texture;
-use cgmath::Vector3;
+use crate::vertex::ChunkVertex;
+use crate::world::breaking::Breaking;
+use crate::world::fluid::Fluid;
+use crate::world::raycast::{raycast, RayHit};
+use crate::world::tick::TickLoop;
+use crate::world::World;
+use cgmath::{EuclideanSpace, InnerSpace};
 use std::iter;
 use wgpu_glyph::{GlyphBrushBuilder, Section, Text};
 use winit::event::ElementState;
 use winit::event::KeyboardInput;
 use winit::event::MouseButton;
+use winit::event::VirtualKeyCode;
 use winit::event::WindowEvent;
 #[derive(Debug)]
 pub struct State {
     pub engine: crate::utils::Engine,
     pub camera: camera::Camera,
     pub light: light::Light,
-    pub chunks: Vec<Chunk>,
+    pub world: World,
+    /// Chunks streamed around the camera
+    pub chunks: ChunkManager,
+    /// Game time of the world, see [`crate::world::tick`]
+    pub ticks: TickLoop,
     depth_texture: texture::Texture,
     pub mouse_pressed: bool,
-    render_pipeline: wgpu::RenderPipeline,
-    materials: Vec<model::Material>,
+    /// Mouse motion since the left button was pressed
+    mouse_drag: f64,
+    /// The block in the middle of the view within reach
+    pub target: Option<RayHit>,
+    /// Progress of breaking the target
+    pub breaking: Breaking,
+    /// Index of the placed block in [`State::HOTBAR`]
+    pub selected: usize,
+    /// Skip chunks hidden behind solid blocks, see [`crate::chunk::visibility`]
+    pub cave_culling: bool,
+    /// Opaque, cutout and translucent block pipelines, indexed by [`RenderType::index`].
+    /// Water is drawn by [`water::Water`]
+    chunk_pipelines: [wgpu::RenderPipeline; 3],
+    /// Texture arrays of all the blocks, a layer per block material
+    block_material: model::Material,
     shadow: shadow::Shadow,
     light_render_pipeline: wgpu::RenderPipeline,
     skybox: sky::Sky,
+    water: water::Water,
+    outline: outline::Outline,
+    damage: damage::Damage,
     glyph_brush: wgpu_glyph::GlyphBrush<wgpu::DepthStencilState>,
     fps: Vec<f32>,
 }
 impl State {
+    /// Render distance in chunks at the start
+    pub const RENDER_DISTANCE: i32 = 8;
+    /// Chunks around the spawn kept loaded, see [`LoadTicket`]
+    pub const SPAWN_RADIUS: i32 = 2;
+    /// Farthest block that can be broken or placed on
+    pub const REACH: f32 = 8.0;
+    /// Mouse motion of a held left button that still breaks the target, a longer drag only turns the camera
+    pub const BREAK_DRAG: f64 = 4.0;
+    /// Blocks the keys F1 to F9 select for placing
+    pub const HOTBAR: [&'static str; 9] = [
+        "stone",
+        "dirt",
+        "grass",
+        "oak_log",
+        "glass",
+        "glowstone",
+        "sand",
+        "water",
+        "lava",
+    ];
     pub fn new(engine: crate::utils::Engine) -> Self {
         let camera = camera::Camera::new(&engine);
         let light = light::Light::new(&engine);
@@ -47,7 +98,7 @@ impl State {
                             visibility: wgpu::ShaderStages::FRAGMENT,
                             ty: wgpu::BindingType::Texture {
                                 multisampled: false,
-                                view_dimension: wgpu::TextureViewDimension::D2,
+                                view_dimension: wgpu::TextureViewDimension::D2Array,
                                 sample_type: wgpu::TextureSampleType::Float { filterable: true },
                             },
                             count: None,
@@ -66,7 +117,7 @@ impl State {
                             ty: wgpu::BindingType::Texture {
                                 multisampled: false,
                                 sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                                view_dimension: wgpu::TextureViewDimension::D2,
+                                view_dimension: wgpu::TextureViewDimension::D2Array,
                             },
                             count: None,
                         },
@@ -82,7 +133,7 @@ impl State {
                             ty: wgpu::BindingType::Texture {
                                 multisampled: false,
                                 sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                                view_dimension: wgpu::TextureViewDimension::D2,
+                                view_dimension: wgpu::TextureViewDimension::D2Array,
                             },
                             count: None,
                         },
@@ -95,20 +146,14 @@ impl State {
                     ],
                     label: Some("texture_bind_group_layout"),
                 });
-        let materials = crate::utils::load_materials(&engine, &texture_bind_group_layout);
-        let mut chunks = vec![];
-        for x in 0..15 {
-            for z in 0..15 {
-                let position = Vector3::new(
-                    (x * Chunk::WIDTH) as f32 - 8.0,
-                    0.0,
-                    (z * Chunk::WIDTH) as f32 - 8.0,
-                );
-                let chunk = Chunk::new(position, &engine.device);
-                chunks.push(chunk);
-            }
-        }
-        let render_pipeline = {
+        let block_material = crate::utils::load_block_material(&engine, &texture_bind_group_layout);
+        let seed = 1982;
+        let mut world = World::empty(seed);
+        let mut chunks = ChunkManager::new(Self::RENDER_DISTANCE, Mesher::Greedy, &engine.device);
+        // the spawn area stays loaded wherever the camera flies
+        chunks.add_ticket(LoadTicket::new(ChunkPos::new(0, 0), Self::SPAWN_RADIUS));
+        chunks.update(&mut world, &camera.model, &engine.device, &engine.queue);
+        let chunk_pipelines = {
             let layout = engine
                 .device
                 .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
@@ -121,19 +166,30 @@ impl State {
                     ],
                     push_constant_ranges: &[],
                 });
-            let shader = wgpu::ShaderModuleDescriptor {
-                label: Some("Normal Shader"),
-                source: wgpu::ShaderSource::Wgsl(include_str!("shaders/shader.wgsl").into()),
-            };
-            crate::utils::create_render_pipeline(
-                &engine.device,
-                &layout,
-                engine.config.format,
-                Some(texture::Texture::DEPTH_FORMAT),
-                &[model::ModelVertex::desc(), instance::InstanceRaw::desc()],
-                shader,
-                Some("Color Render Pipeline"),
-            )
+            let shader = engine
+                .device
+                .create_shader_module(wgpu::ShaderModuleDescriptor {
+                    label: Some("Normal Shader"),
+                    source: wgpu::ShaderSource::Wgsl(
+                        ChunkVertex::shader_source(include_str!("shaders/shader.wgsl")).into(),
+                    ),
+                });
+            [
+                RenderType::Opaque,
+                RenderType::Cutout,
+                RenderType::Translucent,
+            ]
+            .map(|render| {
+                crate::utils::create_chunk_pipeline(
+                    &engine.device,
+                    &layout,
+                    engine.config.format,
+                    Some(texture::Texture::DEPTH_FORMAT),
+                    &[ChunkVertex::desc(), instance::InstanceRaw::desc()],
+                    &shader,
+                    render,
+                )
+            })
         };
         let light_render_pipeline = {
             let layout = engine
@@ -171,6 +227,15 @@ impl State {
             .join("compressed")
             .join("mc_skybox.dds");
         let skybox = sky::Sky::new(file_path, &engine, &camera.bind_group_layout);
+        let water = water::Water::new(
+            &engine,
+            &texture_bind_group_layout,
+            &camera.bind_group_layout,
+            &light.bind_group_layout,
+            &skybox,
+        );
+        let outline = outline::Outline::new(&engine, &camera.bind_group_layout);
+        let damage = damage::Damage::new(&engine, &camera.bind_group_layout);
 
         let glyph_brush = {
             let font = wgpu_glyph::ab_glyph::FontArc::try_from_slice(include_bytes!(
@@ -187,24 +252,124 @@ impl State {
                 })
                 .build(&engine.device, wgpu::TextureFormat::Bgra8UnormSrgb)
         };
+        let ticks = TickLoop::new(world.seed());
         Self {
             engine,
             camera,
             light,
+            world,
             chunks,
+            ticks,
             depth_texture,
             mouse_pressed: false,
-            render_pipeline,
+            mouse_drag: 0.0,
+            target: None,
+            breaking: Breaking::new(),
+            selected: 0,
+            cave_culling: true,
+            chunk_pipelines,
             light_render_pipeline,
-            materials,
+            block_material,
             shadow,
             skybox,
+            water,
+            outline,
+            damage,
             glyph_brush,
             fps: Vec::new(),
         }
     }
     pub fn input(&mut self, event: &WindowEvent) -> bool {
         match event {
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        virtual_keycode: Some(VirtualKeyCode::G),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => {
+                // switch every chunk to the other mesher
+                let mesher = self.chunks.mesher.toggle();
+                log::info!("chunk mesher {:?}", mesher);
+                self.chunks.set_mesher(
+                    mesher,
+                    &self.world,
+                    &self.engine.device,
+                    &self.engine.queue,
+                );
+                true
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        virtual_keycode: Some(VirtualKeyCode::L),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => {
+                let lod_distance = match self.chunks.lod_distance() {
+                    Some(_) => None,
+                    None => Some(ChunkManager::LOD_DISTANCE),
+                };
+                self.chunks.set_lod_distance(lod_distance);
+                true
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        virtual_keycode: Some(VirtualKeyCode::C),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => {
+                self.cave_culling = !self.cave_culling;
+                log::info!("cave culling {}", self.cave_culling);
+                true
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        virtual_keycode:
+                            Some(key @ (VirtualKeyCode::Minus | VirtualKeyCode::Equals)),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => {
+                let step = if *key == VirtualKeyCode::Minus { -1 } else { 1 };
+                let distance = self.chunks.render_distance() + step;
+                self.chunks.set_render_distance(distance);
+                true
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        virtual_keycode:
+                            Some(
+                                key @ (VirtualKeyCode::F1
+                                | VirtualKeyCode::F2
+                                | VirtualKeyCode::F3
+                                | VirtualKeyCode::F4
+                                | VirtualKeyCode::F5
+                                | VirtualKeyCode::F6
+                                | VirtualKeyCode::F7
+                                | VirtualKeyCode::F8
+                                | VirtualKeyCode::F9),
+                            ),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => {
+                // the number keys belong to the light controller
+                self.selected = *key as usize - VirtualKeyCode::F1 as usize;
+                log::info!("selected block {}", Self::HOTBAR[self.selected]);
+                true
+            }
             WindowEvent::KeyboardInput {
                 input:
                     KeyboardInput {
@@ -228,11 +393,102 @@ impl State {
                 ..
             } => {
                 self.mouse_pressed = *state == ElementState::Pressed;
+                self.mouse_drag = 0.0;
+                true
+            }
+            WindowEvent::MouseInput {
+                button: MouseButton::Right,
+                state: ElementState::Pressed,
+                ..
+            } => {
+                self.place_block();
                 true
             }
             _ => false,
         }
     }
+    /// Turns the camera while the left button is down
+    pub fn mouse_motion(&mut self, dx: f64, dy: f64) {
+        if self.mouse_pressed {
+            self.mouse_drag += dx.abs() + dy.abs();
+            self.camera.controller.process_mouse(dx, dy);
+        }
+    }
+    /// Keeps breaking the target while the left button is held still, see [`Breaking`]
+    fn update_breaking(&mut self, dt: std::time::Duration) {
+        let hit = match self.target {
+            Some(hit) if self.mouse_pressed && self.mouse_drag < Self::BREAK_DRAG => hit,
+            _ => {
+                self.breaking.stop();
+                return;
+            }
+        };
+        let kind = self.world.get_block(hit.pos).unwrap_or(BlockKind::AIR);
+        if self.breaking.update(hit.pos, kind, dt.as_secs_f32()) {
+            self.edit_block(hit.pos, BlockKind::AIR);
+        }
+    }
+    /// Places the selected block in front of the targeted face,
+    /// only into air or a liquid and not where the camera is
+    pub fn place_block(&mut self) {
+        let pos = match self.target {
+            Some(hit) => hit.adjacent(),
+            None => return,
+        };
+        let free = self
+            .world
+            .get_block(pos)
+            .is_some_and(|kind| kind == BlockKind::AIR || kind.is_liquid());
+        let eye = BlockPos::containing(self.camera.model.position.to_vec());
+        if !free || pos.split().is_none() || pos == eye {
+            return;
+        }
+        let kind = BlockKind::named(Self::HOTBAR[self.selected]);
+        match Fluid::of(kind) {
+            Some(fluid) => {
+                for coords in self.ticks.fluids.place(&mut self.world, pos, fluid) {
+                    self.remesh(coords);
+                }
+                self.ticks.block_changed(&self.world, pos);
+            }
+            None => self.edit_block(pos, kind),
+        }
+    }
+    /// Changes a block by hand, the chunks are re-meshed at once and the blocks around it
+    /// react on the next ticks
+    fn edit_block(&mut self, pos: BlockPos, kind: BlockKind) {
+        self.set_block(pos, kind);
+        self.ticks.block_changed(&self.world, pos);
+        self.update_target();
+    }
+    /// Casts the view ray for the targeted block
+    fn update_target(&mut self) {
+        self.target = raycast(
+            &self.world,
+            self.camera.model.position.to_vec(),
+            self.camera.model.look_at_coord(),
+            Self::REACH,
+        );
+    }
+    /// Re-builds the mesh of a chunk if it is drawn
+    pub fn remesh(&mut self, coords: ChunkPos) {
+        self.chunks
+            .remesh(&self.world, coords, &self.engine.device, &self.engine.queue);
+    }
+    /// Replaces the blocks of a loaded chunk,
+    /// the neighbours are re-meshed as their border faces may become hidden
+    pub fn insert_chunk(&mut self, coords: ChunkPos, map: ChunkStorage) {
+        self.world.insert_chunk(coords, map);
+        for pos in ChunkManager::around(coords, 1) {
+            self.remesh(pos);
+        }
+    }
+    /// Changes a block and re-meshes every chunk which can see the change
+    pub fn set_block(&mut self, pos: BlockPos, kind: BlockKind) {
+        for coords in self.world.set_block(pos, kind) {
+            self.remesh(coords);
+        }
+    }
     pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
         if new_size.width > 0 && new_size.height > 0 {
             self.engine.config.width = new_size.width;
@@ -262,11 +518,37 @@ impl State {
         self.camera
             .uniform
             .update_view_proj(&self.camera.model, &self.camera.projection);
+        let density =
+            if water::Water::is_underwater(&self.world, self.camera.model.position.to_vec()) {
+                water::Water::FOG_DENSITY
+            } else {
+                0.0
+            };
+        self.camera
+            .uniform
+            .set_fog(water::Water::FOG_COLOR, density);
         self.engine.queue.write_buffer(
             &self.camera.buffer,
             0,
             bytemuck::cast_slice(&[self.camera.uniform]),
         );
+        self.chunks.update(
+            &mut self.world,
+            &self.camera.model,
+            &self.engine.device,
+            &self.engine.queue,
+        );
+        self.water.update(&self.engine.queue, dt);
+        // ticks change blocks all over the loaded chunks, they are re-meshed on the workers
+        for pos in self.ticks.advance(&mut self.world, dt) {
+            self.chunks.queue_remesh(pos);
+        }
+        self.update_target();
+        self.update_breaking(dt);
+        self.damage
+            .update(&self.engine.queue, self.breaking.stage());
+        self.outline
+            .update(&self.engine.queue, self.target.map(|hit| hit.pos));
         // Update the light
         self.light.controller.update_light(&mut self.light.model);
         self.light.projection.w = self.light.controller.orto_w;
@@ -294,6 +576,70 @@ impl State {
                 .create_command_encoder(&wgpu::CommandEncoderDescriptor {
                     label: Some("Render Encoder"),
                 });
+        // chunks outside of the view volumes are neither drawn nor counted
+        let camera_frustum = Frustum::from_matrix(
+            self.camera.projection.calc_matrix() * self.camera.model.calc_matrix(),
+        );
+        let light_frustum = Frustum::from_matrix(
+            self.light.projection.calc_matrix() * self.light.model.calc_matrix(),
+        );
+        let visible = self
+            .cave_culling
+            .then(|| self.chunks.visible(&self.camera.model));
+        let (mut drawn, mut culled, mut occluded) = (0, 0, 0);
+        let (mut shadow_drawn, mut shadow_culled) = (0, 0);
+        let mut shadow_draws = Vec::new();
+        let mut draws = [(); RenderType::COUNT].map(|_| Vec::new());
+        let (mut translucent, mut liquid) = (Vec::new(), Vec::new());
+        let eye = self.camera.model.position.to_vec();
+        for chunk in self.chunks.iter() {
+            if let Some(mesh) = &chunk.mesh {
+                let buffers = &self.chunks.buffers;
+                if light_frustum.intersects(&chunk.aabb) {
+                    // the shadow pipeline has no fragment stage to discard the clear texels
+                    // of cutout blocks, they let the light through like translucent ones
+                    shadow_draws.extend(buffers.command(mesh, RenderType::Opaque));
+                    shadow_drawn += 1;
+                } else {
+                    shadow_culled += 1;
+                }
+                if !camera_frustum.intersects(&chunk.aabb) {
+                    culled += 1;
+                } else if visible
+                    .as_ref()
+                    .is_some_and(|visible| !visible.contains(&chunk.coords))
+                {
+                    occluded += 1;
+                } else {
+                    for render in [RenderType::Opaque, RenderType::Cutout] {
+                        draws[render.index()].extend(buffers.command(mesh, render));
+                    }
+                    let distance = (chunk.aabb.center() - eye).magnitude2();
+                    if let Some(command) = buffers.command(mesh, RenderType::Translucent) {
+                        translucent.push((distance, command));
+                    }
+                    if let Some(command) = buffers.command(mesh, RenderType::Water) {
+                        liquid.push((distance, command));
+                    }
+                    drawn += 1;
+                }
+            }
+        }
+        // translucent chunks and water are blended back to front
+        for (render, mut sorted) in [
+            (RenderType::Translucent, translucent),
+            (RenderType::Water, liquid),
+        ] {
+            sorted.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+            draws[render.index()] = sorted.into_iter().map(|(_, command)| command).collect();
+        }
+        let [opaque, cutout, translucent, liquid] = draws;
+        // every chunk of a pass is drawn from the shared buffers at once
+        let [shadow_batch, opaque, cutout, translucent, liquid] = self.chunks.buffers.batches(
+            &self.engine.device,
+            &self.engine.queue,
+            [shadow_draws, opaque, cutout, translucent, liquid],
+        );
         {
             let mut shadow_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Shadow render pass"),
@@ -316,16 +662,9 @@ impl State {
                 }),
             });
             shadow_pass.set_pipeline(&self.shadow.pipeline);
-            for chunk in self.chunks.iter() {
-                shadow_pass.set_vertex_buffer(1, chunk.instance_buffer.slice(..));
-                for mesh in &chunk.meshes {
-                    shadow_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
-                    shadow_pass
-                        .set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
-                    shadow_pass.set_bind_group(0, &self.light.bind_group, &[]);
-                    shadow_pass.draw_indexed(0..mesh.num_elements, 0, 0..1);
-                }
-            }
+            shadow_pass.set_bind_group(0, &self.light.bind_group, &[]);
+            self.chunks.buffers.bind(&mut shadow_pass);
+            self.chunks.buffers.draw(&mut shadow_pass, &shadow_batch);
         }
         {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
@@ -363,21 +702,17 @@ impl State {
             // /
             // / RENDER CHUNK
             // /
-            render_pass.set_pipeline(&self.render_pipeline);
-            for chunk in self.chunks.iter() {
-                render_pass.set_vertex_buffer(1, chunk.instance_buffer.slice(..));
-                render_pass.set_bind_group(3, &self.shadow.bind_group, &[]);
-                for mesh in &chunk.meshes {
-                    let material = &self.materials[mesh.material];
-
-                    render_pass.draw_mesh(
-                        &mesh,
-                        &material,
-                        &self.camera.bind_group,
-                        &self.light.bind_group,
-                    );
-                }
-            }
+            // block textures are layers of the same texture arrays,
+            // so every chunk is drawn with a single call and the same bind groups
+            render_pass.set_bind_group(0, &self.block_material.bind_group, &[]);
+            render_pass.set_bind_group(1, &self.camera.bind_group, &[]);
+            render_pass.set_bind_group(2, &self.light.bind_group, &[]);
+            render_pass.set_bind_group(3, &self.shadow.bind_group, &[]);
+            self.chunks.buffers.bind(&mut render_pass);
+            render_pass.set_pipeline(&self.chunk_pipelines[RenderType::Opaque.index()]);
+            self.chunks.buffers.draw(&mut render_pass, &opaque);
+            render_pass.set_pipeline(&self.chunk_pipelines[RenderType::Cutout.index()]);
+            self.chunks.buffers.draw(&mut render_pass, &cutout);
             // /
             // / SKYBOX REDNER
             // /
@@ -385,6 +720,40 @@ impl State {
             render_pass.set_bind_group(0, &self.camera.bind_group, &[]);
             render_pass.set_bind_group(1, &self.skybox.bind_group, &[]);
             render_pass.draw(0..3, 0..1);
+            // /
+            // / RENDER TRANSLUCENT
+            // /
+            // drawn over the sky, they test against the depth of the opaque blocks
+            // but do not write it so the blocks behind them stay visible
+            render_pass.set_pipeline(&self.chunk_pipelines[RenderType::Translucent.index()]);
+            render_pass.set_bind_group(0, &self.block_material.bind_group, &[]);
+            render_pass.set_bind_group(1, &self.camera.bind_group, &[]);
+            self.chunks.buffers.bind(&mut render_pass);
+            self.chunks.buffers.draw(&mut render_pass, &translucent);
+            // /
+            // / RENDER WATER
+            // /
+            render_pass.set_pipeline(&self.water.pipeline);
+            render_pass.set_bind_group(3, &self.water.bind_group, &[]);
+            self.chunks.buffers.draw(&mut render_pass, &liquid);
+            // /
+            // / RENDER DAMAGE
+            // /
+            if self.damage.block.is_some() {
+                render_pass.set_pipeline(&self.damage.pipeline);
+                render_pass.set_bind_group(0, &self.camera.bind_group, &[]);
+                render_pass.set_bind_group(1, &self.damage.bind_group, &[]);
+                render_pass.draw(0..damage::Damage::VERTICES, 0..1);
+            }
+            // /
+            // / RENDER OUTLINE
+            // /
+            if self.outline.block.is_some() {
+                render_pass.set_pipeline(&self.outline.pipeline);
+                render_pass.set_bind_group(0, &self.camera.bind_group, &[]);
+                render_pass.set_bind_group(1, &self.outline.bind_group, &[]);
+                render_pass.draw(0..outline::Outline::VERTICES, 0..1);
+            }
         }
 
         {
@@ -394,13 +763,33 @@ impl State {
             let fps: f32 = self.fps.len() as f32 / if sum > 0.0 { sum } else { 1.0 };
 
             let look_at_coord = self.camera.model.look_at_coord();
+            let stats = self.chunks.buffers.stats();
+            let mib = |bytes: u64| bytes as f32 / (1 << 20) as f32;
             self.glyph_brush.queue(Section {
                 screen_position: (30.0, 30.0),
                 text: vec![Text::default()
                     .with_text(
                         format!(
-                            "FPS: {}\n\nCamera pos {:?}\n\nCamera target {:?}",
-                            fps, self.camera.model.position, look_at_coord
+                            "FPS: {}\n\nCamera pos {:?}\n\nCamera target {:?}\n\nChunks {} loaded, {} pending (render distance {})\n\nDrawn {}, culled {}, occluded {}\n\nShadow drawn {}, culled {}\n\nVertices {:.1} of {:.1} MiB ({:.0}% fragmented), indices {:.1} of {:.1} MiB ({:.0}% fragmented)\n\nTarget {:?}, placing {}",
+                            fps,
+                            self.camera.model.position,
+                            look_at_coord,
+                            self.chunks.len(),
+                            self.chunks.pending(),
+                            self.chunks.render_distance(),
+                            drawn,
+                            culled,
+                            occluded,
+                            shadow_drawn,
+                            shadow_culled,
+                            mib(stats.vertices.used),
+                            mib(stats.vertices.capacity),
+                            stats.vertices.fragmentation() * 100.0,
+                            mib(stats.indices.used),
+                            mib(stats.indices.capacity),
+                            stats.indices.fragmentation() * 100.0,
+                            self.target.map(|hit| hit.pos),
+                            Self::HOTBAR[self.selected],
                         )
                         .as_str(),
                     )
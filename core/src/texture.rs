@@ -1,3 +1,4 @@
+use crate::block::registry;
 use crate::model::Material;
 use anyhow::*;
 use image::GenericImageView;
@@ -165,8 +166,94 @@ impl Texture {
 
         let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
         let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-            address_mode_u: wgpu::AddressMode::ClampToEdge,
-            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            // greedy meshed chunk quads have texture coordinates above 1.0,
+            // the texture is repeated once per voxel
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::Repeat,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Nearest,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        Ok(Self {
+            texture,
+            view,
+            sampler,
+        })
+    }
+    /// A 2D texture array with a layer per image, every image has to be of the same size
+    pub fn from_images(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        images: &[image::DynamicImage],
+        label: Option<&str>,
+        is_normal_map: bool,
+    ) -> Result<Self> {
+        ensure!(
+            !images.is_empty(),
+            "a texture array needs at least one image"
+        );
+        let dimensions = images[0].dimensions();
+        ensure!(
+            images.iter().all(|img| img.dimensions() == dimensions),
+            "every layer of a texture array has to be {:?}",
+            dimensions
+        );
+        let size = wgpu::Extent3d {
+            width: dimensions.0,
+            height: dimensions.1,
+            depth_or_array_layers: images.len() as u32,
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label,
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: if is_normal_map {
+                wgpu::TextureFormat::Rgba8Unorm
+            } else {
+                wgpu::TextureFormat::Rgba8UnormSrgb
+            },
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        });
+        for (layer, img) in images.iter().enumerate() {
+            queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    aspect: wgpu::TextureAspect::All,
+                    texture: &texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d {
+                        x: 0,
+                        y: 0,
+                        z: layer as u32,
+                    },
+                },
+                &img.to_rgba8(),
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: NonZeroU32::new(4 * dimensions.0),
+                    rows_per_image: NonZeroU32::new(dimensions.1),
+                },
+                wgpu::Extent3d {
+                    depth_or_array_layers: 1,
+                    ..size
+                },
+            );
+        }
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            label,
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            // greedy meshed chunk quads have texture coordinates above 1.0,
+            // the texture is repeated once per voxel
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::Repeat,
             address_mode_w: wgpu::AddressMode::ClampToEdge,
             mag_filter: wgpu::FilterMode::Linear,
             min_filter: wgpu::FilterMode::Nearest,
@@ -263,14 +350,6 @@ impl Texture {
     }
 }
 
-pub const TEXTURE_NAMES: &'static [[&str; 3]; 6] = &[
-    ["bedrock", "bedrock_n", "bedrock_s"],
-    ["brick", "brick_n", "brick_s"],
-    ["planks_oak", "planks_oak_n", "planks_oak_s"],
-    ["dirt", "dirt_n", "dirt_s"],
-    ["snow", "snow_n", "snow_s"],
-    ["stone", "stone_n", "stone_s"],
-];
 pub fn load_textures<P>(
     device: &wgpu::Device,
     queue: &wgpu::Queue,
@@ -283,7 +362,9 @@ where
     let root_dir = Path::new(&p);
     // let names = vec!["texture_pack"];
     let mut out = vec![];
-    for [name_diff, name_nor, name_spec] in TEXTURE_NAMES {
+    for name in registry().textures() {
+        let (name_diff, name_nor, name_spec) =
+            (name, format!("{}_n", name), format!("{}_s", name));
         let diffuse_texture = {
             let path_diff = root_dir
                 .join(format!("{}.png", name_diff).as_str());
@@ -351,3 +432,73 @@ where
     }
     out
 }
+
+/// Loads all the block textures into diffuse, normal and specular texture arrays
+/// of a single material, layer `i` holds the textures of `registry().textures()[i]`
+pub fn load_texture_array<P>(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    layout: &wgpu::BindGroupLayout,
+    p: P,
+) -> Result<Material>
+where
+    P: AsRef<Path> + AsRef<OsStr>,
+{
+    let root_dir = Path::new(&p);
+    // diffuse, normal and specular textures of a block texture differ in suffix only
+    let load = |suffix: &str| -> Result<Vec<image::DynamicImage>> {
+        registry()
+            .textures()
+            .iter()
+            .map(|name| {
+                let path = root_dir.join(format!("{}{}.png", name, suffix));
+                image::io::Reader::open(&path)?
+                    .decode()
+                    .with_context(|| format!("can not decode {:?}", path))
+            })
+            .collect()
+    };
+    let diffuse_texture =
+        Texture::from_images(device, queue, &load("")?, Some("Block diffuse array"), false)?;
+    let normal_texture =
+        Texture::from_images(device, queue, &load("_n")?, Some("Block normal array"), true)?;
+    let specular_texture =
+        Texture::from_images(device, queue, &load("_s")?, Some("Block specular array"), true)?;
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&diffuse_texture.view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(&diffuse_texture.sampler),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: wgpu::BindingResource::TextureView(&normal_texture.view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 3,
+                resource: wgpu::BindingResource::Sampler(&normal_texture.sampler),
+            },
+            wgpu::BindGroupEntry {
+                binding: 4,
+                resource: wgpu::BindingResource::TextureView(&specular_texture.view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 5,
+                resource: wgpu::BindingResource::Sampler(&specular_texture.sampler),
+            },
+        ],
+        label: Some("Block texture array bind group"),
+    });
+    Ok(Material {
+        name: "blocks".to_string(),
+        diffuse_texture,
+        normal_texture,
+        specular_texture,
+        bind_group,
+    })
+}
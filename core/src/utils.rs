@@ -1,7 +1,7 @@
 use cgmath::{InnerSpace, Vector2, Vector3};
 
-use crate::chunk::Chunk;
-use crate::{model, Position};
+use crate::block::RenderType;
+use crate::model;
 
 pub fn calc_normals(v: &[&model::ModelVertex]) -> ([[f32; 3]; 3], [[f32; 3]; 3], [[f32; 3]; 3]) {
     let ia_position = Vector3::from(v[0].position);
@@ -64,25 +64,6 @@ pub fn calc_normals(v: &[&model::ModelVertex]) -> ([[f32; 3]; 3], [[f32; 3]; 3],
 }
 
 #[allow(dead_code)]
-pub fn to_index(position: &Position) -> usize {
-    log::trace!("convert position {:?} to index", position);
-    let Position { x, y, z } = position;
-    let index = Chunk::WIDTH * Chunk::WIDTH * y.clone() as usize
-        + Chunk::WIDTH * z.clone() as usize
-        + x.clone() as usize;
-    log::trace!("calculated index is {}", index);
-    index
-}
-
-#[allow(dead_code)]
-pub fn to_position(index: usize) -> Position {
-    log::trace!("convert index {} to position", index);
-    let x = index % Chunk::WIDTH;
-    let y = ((index - x) / Chunk::WIDTH) % Chunk::WIDTH;
-    let z = (index - x - Chunk::WIDTH * y) / (Chunk::WIDTH * Chunk::WIDTH);
-    [x as f32, z as f32, y as f32].into()
-}
-
 pub async fn get_engine(window: &winit::window::Window) -> Engine {
     let size = window.inner_size();
 
@@ -99,6 +80,8 @@ pub async fn get_engine(window: &winit::window::Window) -> Engine {
         .await
         .unwrap();
 
+    // chunks are drawn with indirect draws when the adapter supports them
+    let indirect = adapter.features() & crate::chunk::buffers::DrawMode::FEATURES;
     let (device, queue) = adapter
         .request_device(
             &wgpu::DeviceDescriptor {
@@ -107,7 +90,8 @@ pub async fn get_engine(window: &winit::window::Window) -> Engine {
                     | wgpu::Features::DEPTH_CLIP_CONTROL
                     // | wgpu::Features::TEXTURE_COMPRESSION_ASTC_LDR
                     // | wgpu::Features::TEXTURE_COMPRESSION_ETC2
-                    | wgpu::Features::TEXTURE_COMPRESSION_BC,
+                    | wgpu::Features::TEXTURE_COMPRESSION_BC
+                    | indirect,
                 limits: wgpu::Limits::default(),
             },
             None, // Trace path
@@ -166,6 +150,20 @@ pub fn load_materials(
     materials
 }
 
+/// Block textures as a single material of texture arrays, see [`crate::texture::load_texture_array`]
+pub fn load_block_material(
+    engine: &Engine,
+    bind_group_layout: &wgpu::BindGroupLayout,
+) -> model::Material {
+    let out_dir = std::env::var("OUT_DIR").unwrap();
+    let root_path = std::path::Path::new(&out_dir)
+        .join("res")
+        .join("textures")
+        .join("blocks");
+    crate::texture::load_texture_array(&engine.device, &engine.queue, bind_group_layout, root_path)
+        .unwrap()
+}
+
 pub fn create_render_pipeline(
     device: &wgpu::Device,
     layout: &wgpu::PipelineLayout,
@@ -225,6 +223,83 @@ pub fn create_render_pipeline(
     })
 }
 
+/// Pipeline for the chunk geometry of one render type. Cutout blocks discard their
+/// transparent texels, translucent blocks and water are blended and leave the depth buffer alone.
+/// Water is seen from below as well, its faces are not culled
+pub fn create_chunk_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    color_format: wgpu::TextureFormat,
+    depth_format: Option<wgpu::TextureFormat>,
+    vertex_layouts: &[wgpu::VertexBufferLayout],
+    shader: &wgpu::ShaderModule,
+    render: RenderType,
+) -> wgpu::RenderPipeline {
+    let (label, entry_point, blend) = match render {
+        RenderType::Opaque => (
+            "Opaque Chunk Pipeline",
+            "fs_main",
+            wgpu::BlendState::REPLACE,
+        ),
+        RenderType::Cutout => (
+            "Cutout Chunk Pipeline",
+            "fs_cutout",
+            wgpu::BlendState::REPLACE,
+        ),
+        RenderType::Translucent => (
+            "Translucent Chunk Pipeline",
+            "fs_main",
+            wgpu::BlendState::ALPHA_BLENDING,
+        ),
+        RenderType::Water => (
+            "Water Pipeline",
+            "fs_main",
+            wgpu::BlendState::ALPHA_BLENDING,
+        ),
+    };
+    let blended = matches!(render, RenderType::Translucent | RenderType::Water);
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(label),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: "vs_main",
+            buffers: vertex_layouts,
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point,
+            targets: &[Some(wgpu::ColorTargetState {
+                format: color_format,
+                blend: Some(blend),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: (render != RenderType::Water).then(|| wgpu::Face::Back),
+            polygon_mode: wgpu::PolygonMode::Fill,
+            unclipped_depth: false,
+            conservative: false,
+        },
+        depth_stencil: depth_format.map(|format| wgpu::DepthStencilState {
+            format,
+            depth_write_enabled: !blended,
+            depth_compare: wgpu::CompareFunction::Less,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState {
+            count: 1,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+    })
+}
+
 pub fn create_skybox_pipeline(
     device: &wgpu::Device,
     layout: &wgpu::PipelineLayout,
@@ -271,6 +346,54 @@ pub fn create_skybox_pipeline(
     })
 }
 
+/// Geometry drawn over the blocks without writing the depth,
+/// see [`crate::data::outline`] and [`crate::data::damage`]
+#[allow(clippy::too_many_arguments)]
+pub fn create_overlay_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    color_format: wgpu::TextureFormat,
+    depth_format: Option<wgpu::TextureFormat>,
+    shader: wgpu::ShaderModuleDescriptor,
+    topology: wgpu::PrimitiveTopology,
+    blend: wgpu::BlendState,
+    label: Option<&str>,
+) -> wgpu::RenderPipeline {
+    let shader = device.create_shader_module(shader);
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label,
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: "vs_main",
+            buffers: &[],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: "fs_main",
+            targets: &[Some(wgpu::ColorTargetState {
+                format: color_format,
+                blend: Some(blend),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology,
+            ..Default::default()
+        },
+        depth_stencil: depth_format.map(|format| wgpu::DepthStencilState {
+            format,
+            depth_write_enabled: false,
+            depth_compare: wgpu::CompareFunction::LessEqual,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+    })
+}
+
 pub fn create_shadow_render_pipeline(
     device: &wgpu::Device,
     layout: &wgpu::PipelineLayout,
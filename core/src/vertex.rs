@@ -28,3 +28,136 @@ impl Vertex {
         }
     }
 }
+
+/// Vertex of a chunk mesh packed into two words, it is decoded in the shaders.
+///
+/// ```text
+/// data[0]: x 5 bits | y 7 bits | z 5 bits | face 3 bits | ao 2 bits | lowered 1 bit
+/// data[1]: u 7 bits | v 7 bits | unused 2 bits | material 16 bits
+/// ```
+///
+/// Positions are local to the chunk and include the far edge of the chunk,
+/// normal, tangent and bitangent are looked up by the face index in [`crate::voxel::Voxel::FACES`] order.
+/// Texture coordinates are integer, a texture repeats once per voxel of a merged quad.
+/// Lowered vertices are moved down by [`ChunkVertex::LIQUID_DROP`] in the shaders,
+/// they are the top corners of a liquid surface.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ChunkVertex {
+    pub data: [u32; 2],
+}
+
+impl ChunkVertex {
+    const X_BITS: u32 = 5;
+    const Y_BITS: u32 = 7;
+    const FACE_SHIFT: u32 = 17;
+    const AO_SHIFT: u32 = 20;
+    const LOWERED_SHIFT: u32 = 22;
+    const UV_BITS: u32 = 7;
+    const MATERIAL_SHIFT: u32 = 16;
+    /// How far below the top of its block a liquid surface is
+    pub const LIQUID_DROP: f32 = 0.125;
+
+    /// Source of a shader decoding chunk vertices, with the WGSL constants of the layout
+    /// declared in front of it
+    pub fn shader_source(shader: &str) -> String {
+        format!(
+            "let LIQUID_DROP: f32 = {:?};\n\n{}",
+            Self::LIQUID_DROP,
+            shader
+        )
+    }
+
+    /// `ao` goes from 0 (fully occluded) to 3 (open)
+    pub fn new(
+        position: [u32; 3],
+        face: usize,
+        tex_coords: [u32; 2],
+        material: usize,
+        ao: u8,
+    ) -> Self {
+        // an overflowing field would silently spill into its neighbours
+        assert!(
+            position[0] <= crate::chunk::Chunk::WIDTH as u32
+                && position[1] <= crate::chunk::Chunk::HEIGHT as u32
+                && position[2] <= crate::chunk::Chunk::WIDTH as u32,
+            "vertex position {:?} is outside of a chunk",
+            position
+        );
+        assert!(
+            tex_coords[0] < 1 << Self::UV_BITS && tex_coords[1] < 1 << Self::UV_BITS,
+            "texture coordinates {:?} do not fit into {} bits",
+            tex_coords,
+            Self::UV_BITS
+        );
+        assert!(
+            face < 6 && ao < 4 && material <= u16::MAX as usize,
+            "face {}, ao {} or material {} is out of range",
+            face,
+            ao,
+            material
+        );
+        ChunkVertex {
+            data: [
+                position[0]
+                    | position[1] << Self::X_BITS
+                    | position[2] << (Self::X_BITS + Self::Y_BITS)
+                    | (face as u32) << Self::FACE_SHIFT
+                    | (ao as u32) << Self::AO_SHIFT,
+                tex_coords[0]
+                    | tex_coords[1] << Self::UV_BITS
+                    | (material as u32) << Self::MATERIAL_SHIFT,
+            ],
+        }
+    }
+
+    pub fn position(&self) -> [u32; 3] {
+        let x_mask = (1 << Self::X_BITS) - 1;
+        [
+            self.data[0] & x_mask,
+            (self.data[0] >> Self::X_BITS) & ((1 << Self::Y_BITS) - 1),
+            (self.data[0] >> (Self::X_BITS + Self::Y_BITS)) & x_mask,
+        ]
+    }
+
+    pub fn face(&self) -> usize {
+        ((self.data[0] >> Self::FACE_SHIFT) & 0b111) as usize
+    }
+
+    pub fn ao(&self) -> u8 {
+        ((self.data[0] >> Self::AO_SHIFT) & 0b11) as u8
+    }
+
+    pub fn with_lowered(mut self, lowered: bool) -> Self {
+        self.data[0] =
+            self.data[0] & !(1 << Self::LOWERED_SHIFT) | (lowered as u32) << Self::LOWERED_SHIFT;
+        self
+    }
+
+    pub fn lowered(&self) -> bool {
+        (self.data[0] >> Self::LOWERED_SHIFT) & 1 == 1
+    }
+
+    pub fn tex_coords(&self) -> [u32; 2] {
+        let mask = (1 << Self::UV_BITS) - 1;
+        [self.data[1] & mask, (self.data[1] >> Self::UV_BITS) & mask]
+    }
+
+    pub fn material(&self) -> usize {
+        (self.data[1] >> Self::MATERIAL_SHIFT) as usize
+    }
+}
+
+impl crate::model::Vertex for ChunkVertex {
+    fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<ChunkVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[wgpu::VertexAttribute {
+                offset: 0,
+                shader_location: 0,
+                format: wgpu::VertexFormat::Uint32x2,
+            }],
+        }
+    }
+}
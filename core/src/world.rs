@@ -1,78 +1,300 @@
-use cgmath::Point3;
 use noise::Seedable;
-use num_traits::{FromPrimitive, ToPrimitive};
 use rand::RngCore;
 use rand::{Rng, SeedableRng};
 use rand_chacha::ChaCha8Rng;
-use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write};
 use std::path::Path;
 
-use crate::block::BlockKind;
+use crate::block::{registry, BlockKind};
+use crate::chunk::storage::ChunkStorage;
 use crate::chunk::Chunk;
+use crate::coords::{BlockPos, ChunkPos, LocalPos};
 use crate::texture;
 use crate::utils;
-#[derive(Debug, Serialize, Deserialize)]
+
+pub mod breaking;
+pub mod fluid;
+pub mod raycast;
+pub mod region;
+pub mod save;
+pub mod tick;
+
+#[derive(Debug)]
 pub struct World {
-    chunks: Vec<BlockKind>,
+    seed: u64,
+    chunks: HashMap<ChunkPos, ChunkStorage>,
+    /// Chunks changed since they were read from or written to the region files
+    dirty: HashSet<ChunkPos>,
+    regions: Option<region::Regions>,
 }
 impl World {
-    /// A world size of SIZE x SIZE chunks
+    /// Size of a generated world, SIZE x SIZE chunks
     pub const SIZE: usize = 10;
     /// A chunk has square base of CHUNK_WIDTH*CHUNK_WIDTH
     pub const CHUNK_WIDTH: usize = 16;
     /// Height of a chunk
     pub const CHUNK_HEIGHT: usize = 64;
-    /// World voxel length
+    /// Generated world voxel length
     pub const WORLD_SIZE: usize =
         Self::SIZE * Self::SIZE * Self::CHUNK_WIDTH * Self::CHUNK_WIDTH * Self::CHUNK_HEIGHT;
+    /// Voxel length of a single chunk
+    pub const CHUNK_VOLUME: usize = Self::CHUNK_WIDTH * Self::CHUNK_WIDTH * Self::CHUNK_HEIGHT;
 
     pub fn generate(seed: u64) -> Self {
         let mut rng = ChaCha8Rng::seed_from_u64(seed);
         let perlin_noise = noise::Perlin::new().set_seed(seed as u32);
-        let mut chunks = Vec::new();
-        for _word_x in 0..Self::SIZE {
-            for _word_z in 0..Self::SIZE {
-                let mut chunk =
-                    Self::generate_chunk(&mut rng, Self::CHUNK_WIDTH, Self::CHUNK_HEIGHT);
-                chunks.append(&mut chunk);
+        let mut world = World::empty(seed);
+        for word_x in 0..Self::SIZE {
+            for word_z in 0..Self::SIZE {
+                let chunk = Self::generate_chunk(&mut rng, Self::CHUNK_WIDTH, Self::CHUNK_HEIGHT);
+                world.insert_chunk(ChunkPos::new(word_x as i32, word_z as i32), chunk);
             }
         }
-        World { chunks }
+        world
+    }
+    /// A world without any chunk
+    pub fn empty(seed: u64) -> Self {
+        World {
+            seed,
+            chunks: HashMap::new(),
+            dirty: HashSet::new(),
+            regions: None,
+        }
     }
-    pub fn generate_chunk<T>(rng: &mut T, width: usize, height: usize) -> Vec<BlockKind>
+    pub fn generate_chunk<T>(rng: &mut T, width: usize, height: usize) -> ChunkStorage
     where
         T: SeedableRng + RngCore,
     {
-        let mut blocks = vec![];
+        let mut blocks = ChunkStorage::default();
+        // any visible block of the registry
+        let kinds: Vec<_> = registry()
+            .blocks()
+            .map(|def| BlockKind(def.id))
+            .filter(|kind| registry().is_visible(*kind))
+            .collect();
         for y in 0..height {
             let max_y = rng.gen_range(height / 2..=height);
-            for _x in 0..width {
-                for _z in 0..width {
-                    let block_type = if y > max_y {
-                        0
+            for z in 0..width {
+                for x in 0..width {
+                    let kind = if y > max_y {
+                        BlockKind::AIR
                     } else if y < 2 {
-                        1
+                        BlockKind::named("stone")
                     } else {
-                        let end = BlockKind::COUNT.to_i32().unwrap();
-                        rng.gen_range(1..end)
+                        kinds[rng.gen_range(0..kinds.len())]
                     };
-                    blocks.push(BlockKind::from_i32(block_type).map_or(BlockKind::Air, |x| x));
+                    blocks.set_local(LocalPos::new(x, y, z), kind);
                 }
             }
         }
         blocks
     }
-    pub fn load<P>(path: P) -> Self
+    /// Opens a world saved by [`World::save`] without loading any chunk,
+    /// chunks are read on demand with [`World::load_chunk`]
+    pub fn open<P>(dir: P) -> anyhow::Result<Self>
     where
         P: AsRef<Path>,
     {
-        todo!()
+        let file = File::open(dir.as_ref().join(save::LEVEL_FILE))?;
+        let header = save::Header::read(&mut BufReader::new(file))?;
+        anyhow::ensure!(
+            header.chunk_width as usize == Self::CHUNK_WIDTH
+                && header.chunk_height as usize == Self::CHUNK_HEIGHT,
+            "world was saved with {}x{} chunks, expected {}x{}",
+            header.chunk_width,
+            header.chunk_height,
+            Self::CHUNK_WIDTH,
+            Self::CHUNK_HEIGHT
+        );
+        let mut world = World::empty(header.seed);
+        world.regions = Some(region::Regions::new(dir.as_ref().join(save::REGION_DIR))?);
+        Ok(world)
     }
 
-    pub fn chunks(&self) -> &Vec<BlockKind> {
-        &self.chunks
+    /// Loads a world written by [`World::save`] with all of its chunks
+    pub fn load<P>(dir: P) -> anyhow::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let mut world = Self::open(dir)?;
+        let saved = world.regions.as_mut().unwrap().saved_chunks()?;
+        for coords in saved {
+            world.load_chunk(coords.into())?;
+        }
+        log::debug!("world {} is loaded", world.seed);
+        Ok(world)
+    }
+
+    /// Saves the world into a directory, see [`save`] for the layout.
+    /// Only chunks changed since the last save are written.
+    pub fn save<P>(&mut self, dir: P) -> anyhow::Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        fs::create_dir_all(dir.as_ref())?;
+        let file = File::create(dir.as_ref().join(save::LEVEL_FILE))?;
+        let mut writer = BufWriter::new(file);
+        let header = save::Header {
+            version: save::FORMAT_VERSION,
+            seed: self.seed,
+            chunk_width: Self::CHUNK_WIDTH as u16,
+            chunk_height: Self::CHUNK_HEIGHT as u16,
+        };
+        header.write(&mut writer)?;
+        writer.flush()?;
+
+        let region_dir = dir.as_ref().join(save::REGION_DIR);
+        if self.regions.as_ref().map(|r| r.dir()) != Some(region_dir.as_path()) {
+            // a new save location, all the chunks have to be written there,
+            // the ones which are not in memory are copied from the old location
+            let mut regions = region::Regions::new(region_dir)?;
+            if let Some(old) = self.regions.as_mut() {
+                for (x, z) in old.saved_chunks()? {
+                    if self.chunks.contains_key(&ChunkPos::new(x, z)) {
+                        continue;
+                    }
+                    if let Some(blocks) = old.read_chunk(x, z)? {
+                        regions.write_chunk(x, z, &blocks)?;
+                    }
+                }
+            }
+            self.regions = Some(regions);
+            self.dirty = self.chunks.keys().cloned().collect();
+        }
+        let dirty: Vec<_> = self.dirty.iter().cloned().collect();
+        for coords in dirty {
+            self.save_chunk(coords)?;
+        }
+        Ok(())
+    }
+
+    /// Reads a single chunk from the region files.
+    /// Returns `false` if the chunk was never saved
+    pub fn load_chunk(&mut self, coords: ChunkPos) -> anyhow::Result<bool> {
+        let regions = match self.regions.as_mut() {
+            Some(regions) => regions,
+            None => return Ok(false),
+        };
+        match regions.read_chunk(coords.x, coords.z)? {
+            Some(blocks) => {
+                self.chunks.insert(coords, blocks);
+                self.dirty.remove(&coords);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
     }
-    pub fn get_visible_chuncks(&self, Point3 { x, y, z }: Point3<i32>) -> &[BlockKind] {
-        &self.chunks[0..1]
+
+    /// Writes a single chunk into its region file
+    pub fn save_chunk(&mut self, coords: ChunkPos) -> anyhow::Result<()> {
+        let regions = self
+            .regions
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("world has no save location yet"))?;
+        if let Some(blocks) = self.chunks.get(&coords) {
+            regions.write_chunk(coords.x, coords.z, blocks)?;
+        }
+        self.dirty.remove(&coords);
+        Ok(())
+    }
+
+    /// Removes a chunk from memory, writing it first if it was changed
+    pub fn unload_chunk(&mut self, coords: ChunkPos) -> anyhow::Result<()> {
+        if self.dirty.contains(&coords) && self.regions.is_some() {
+            self.save_chunk(coords)?;
+        }
+        self.dirty.remove(&coords);
+        self.chunks.remove(&coords);
+        Ok(())
+    }
+
+    pub fn insert_chunk(&mut self, coords: ChunkPos, blocks: ChunkStorage) {
+        self.chunks.insert(coords, blocks);
+        self.dirty.insert(coords);
+    }
+
+    pub fn chunk(&self, coords: ChunkPos) -> Option<&ChunkStorage> {
+        self.chunks.get(&coords)
+    }
+
+    /// Mutable access to a chunk, the chunk is marked as changed
+    pub fn chunk_mut(&mut self, coords: ChunkPos) -> Option<&mut ChunkStorage> {
+        let chunk = self.chunks.get_mut(&coords);
+        if chunk.is_some() {
+            self.dirty.insert(coords);
+        }
+        chunk
+    }
+
+    /// Block at a world position, `None` if its chunk is not loaded
+    pub fn get_block(&self, pos: BlockPos) -> Option<BlockKind> {
+        match pos.split() {
+            Some((chunk, local)) => self.chunk(chunk).map(|chunk| chunk.get_local(local)),
+            None => Some(BlockKind::AIR),
+        }
+    }
+
+    /// Level of a flowing liquid at a world position, see [`fluid`].
+    /// 0 for any other block and for blocks of chunks which are not loaded
+    pub fn get_level(&self, pos: BlockPos) -> u8 {
+        match pos.split() {
+            Some((chunk, local)) => self
+                .chunk(chunk)
+                .map_or(0, |chunk| chunk.level_local(local)),
+            None => 0,
+        }
+    }
+
+    /// Sets the level of a block, it is saved with the chunk and reset when the block changes.
+    /// A level change alone does not change the mesh
+    pub fn set_level(&mut self, pos: BlockPos, level: u8) {
+        if let Some((coords, local)) = pos.split() {
+            if let Some(chunk) = self.chunk_mut(coords) {
+                chunk.set_level_local(local, level);
+            }
+        }
+    }
+
+    /// Sets a block at a world position, the level of the block goes back to 0.
+    /// Returns the chunks whose meshes are affected by the change,
+    /// the chunk itself first and then the neighbours sharing the changed border.
+    /// A block in a corner column changes the ambient occlusion of the diagonal chunk too
+    pub fn set_block(&mut self, pos: BlockPos, kind: BlockKind) -> Vec<ChunkPos> {
+        let (coords, local) = match pos.split() {
+            Some(split) => split,
+            None => return vec![],
+        };
+        match self.chunk_mut(coords) {
+            Some(chunk) => chunk.set_local(local, kind),
+            None => return vec![],
+        }
+        let last = Self::CHUNK_WIDTH - 1;
+        let border = |c: usize| match c {
+            0 => -1,
+            c if c == last => 1,
+            _ => 0,
+        };
+        let (dx, dz) = (border(local.x), border(local.z));
+        let mut affected = vec![coords];
+        if dx != 0 {
+            affected.push(coords.offset(dx, 0));
+        }
+        if dz != 0 {
+            affected.push(coords.offset(0, dz));
+        }
+        if dx != 0 && dz != 0 {
+            affected.push(coords.offset(dx, dz));
+        }
+        affected.retain(|c| self.chunks.contains_key(c));
+        affected
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+    pub fn chunks(&self) -> &HashMap<ChunkPos, ChunkStorage> {
+        &self.chunks
     }
 }
@@ -0,0 +1,60 @@
+//! Breaking a block by holding the break button on it.
+//!
+//! Breaking takes the [`BlockDef::hardness`] of the block in seconds and starts over when the
+//! target changes. The progress is shown in [`Breaking::STAGES`] damage stages.
+//!
+//! [`BlockDef::hardness`]: crate::block::BlockDef::hardness
+use crate::block::BlockKind;
+use crate::coords::BlockPos;
+
+#[derive(Debug, Default)]
+pub struct Breaking {
+    /// The block being broken and its kind when breaking started
+    target: Option<(BlockPos, BlockKind)>,
+    /// Seconds spent on the target
+    time: f32,
+}
+
+impl Breaking {
+    pub const STAGES: u32 = 3;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keeps breaking the block of `kind` at `pos` for `dt` more seconds.
+    /// Returns whether it broke, breaking stops then
+    pub fn update(&mut self, pos: BlockPos, kind: BlockKind, dt: f32) -> bool {
+        if self.target != Some((pos, kind)) {
+            self.target = Some((pos, kind));
+            self.time = 0.0;
+        }
+        let hardness = kind.hardness();
+        if hardness < 0.0 {
+            return false;
+        }
+        self.time += dt;
+        if self.time >= hardness {
+            self.stop();
+            return true;
+        }
+        false
+    }
+
+    /// Forgets the progress, the button was released or nothing is targeted
+    pub fn stop(&mut self) {
+        self.target = None;
+        self.time = 0.0;
+    }
+
+    /// The block being broken and its damage stage, `0..STAGES`
+    pub fn stage(&self) -> Option<(BlockPos, u32)> {
+        let (pos, kind) = self.target?;
+        let hardness = kind.hardness();
+        if hardness <= 0.0 {
+            return None;
+        }
+        let stage = (self.time / hardness * Self::STAGES as f32) as u32;
+        Some((pos, stage.min(Self::STAGES - 1)))
+    }
+}
@@ -0,0 +1,271 @@
+//! Flowing liquids as a cellular automaton over the blocks of a [`World`].
+//!
+//! A liquid block is either a source or flowing. Flowing blocks have a level,
+//! 1 next to a source or under any liquid and one more for every block sideways.
+//! A flowing block without a neighbour one level closer to a source dries up.
+//! Levels are kept with the blocks of the chunks, see [`World::get_level`],
+//! so a world saved with flowing liquids keeps flowing the same way after a reload.
+//!
+//! Blocks are only updated when they are scheduled, a changed liquid schedules
+//! its neighbours after the delay of the liquid. Updates of a tick run in block
+//! position order, so a simulation only depends on the world and the tick count.
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::block::BlockKind;
+use crate::coords::{BlockPos, ChunkPos};
+
+use super::World;
+
+lazy_static! {
+    static ref BLOCKS: [BlockKind; 3] = ["water", "lava", "stone"].map(BlockKind::named);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Fluid {
+    Water,
+    Lava,
+}
+
+impl Fluid {
+    pub fn of(kind: BlockKind) -> Option<Fluid> {
+        match kind {
+            kind if kind == BLOCKS[0] => Some(Fluid::Water),
+            kind if kind == BLOCKS[1] => Some(Fluid::Lava),
+            _ => None,
+        }
+    }
+
+    pub fn block(self) -> BlockKind {
+        match self {
+            Fluid::Water => BLOCKS[0],
+            Fluid::Lava => BLOCKS[1],
+        }
+    }
+
+    /// Highest level of a flowing block, how far the liquid spreads sideways
+    pub fn reach(self) -> u8 {
+        match self {
+            Fluid::Water => 7,
+            Fluid::Lava => 3,
+        }
+    }
+
+    /// Ticks between a change and the update of the neighbours
+    pub fn delay(self) -> u64 {
+        match self {
+            Fluid::Water => 5,
+            Fluid::Lava => 30,
+        }
+    }
+}
+
+/// What a tick of the liquids changed, in order and without repeats
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Changes {
+    /// Chunks whose meshes are affected
+    pub chunks: BTreeSet<ChunkPos>,
+    /// Blocks which changed kind, the blocks around them may have to be woken up
+    pub blocks: BTreeSet<BlockPos>,
+}
+
+#[derive(Debug, Default)]
+pub struct Fluids {
+    /// Blocks to update by tick
+    scheduled: BTreeMap<u64, BTreeSet<BlockPos>>,
+    tick: u64,
+}
+
+impl Fluids {
+    /// Neighbours a liquid spreads to sideways
+    const SIDES: [[i32; 3]; 4] = [[1, 0, 0], [-1, 0, 0], [0, 0, 1], [0, 0, -1]];
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ticks run so far
+    pub fn ticks(&self) -> u64 {
+        self.tick
+    }
+
+    /// Blocks waiting for an update
+    pub fn pending(&self) -> usize {
+        self.scheduled.values().map(BTreeSet::len).sum()
+    }
+
+    /// The liquid at a position and its level, 0 for a source
+    pub fn get(&self, world: &World, pos: BlockPos) -> Option<(Fluid, u8)> {
+        let fluid = Fluid::of(world.get_block(pos)?)?;
+        Some((fluid, world.get_level(pos)))
+    }
+
+    /// Places a source block, it starts to flow on the next update.
+    /// Returns the chunks whose meshes are affected
+    pub fn place(&mut self, world: &mut World, pos: BlockPos, fluid: Fluid) -> Vec<ChunkPos> {
+        let changed = world.set_block(pos, fluid.block());
+        self.schedule(pos, fluid.delay());
+        changed
+    }
+
+    /// Wakes up the liquids around a block changed by something else than the simulation
+    pub fn block_changed(&mut self, world: &World, pos: BlockPos) {
+        self.schedule_around(world, pos);
+    }
+
+    /// Updates the block at `pos` after `delay` ticks, `delay` is at least 1
+    pub fn schedule(&mut self, pos: BlockPos, delay: u64) {
+        self.scheduled
+            .entry(self.tick + delay.max(1))
+            .or_default()
+            .insert(pos);
+    }
+
+    /// Runs the updates scheduled for the next tick
+    pub fn tick(&mut self, world: &mut World) -> Changes {
+        self.tick += 1;
+        let mut changed = Changes::default();
+        if let Some(due) = self.scheduled.remove(&self.tick) {
+            for pos in due {
+                self.update(world, pos, &mut changed);
+            }
+        }
+        changed
+    }
+
+    fn update(&mut self, world: &mut World, pos: BlockPos, changed: &mut Changes) {
+        let (fluid, level) = match self.get(world, pos) {
+            Some(liquid) => liquid,
+            None => return,
+        };
+        // lava touching water turns into stone
+        if fluid == Fluid::Lava
+            && [[0, 1, 0], [1, 0, 0], [-1, 0, 0], [0, 0, 1], [0, 0, -1]]
+                .iter()
+                .any(|&[x, y, z]| self.fluid_at(world, pos.offset(x, y, z)) == Some(Fluid::Water))
+        {
+            self.set(world, pos, BLOCKS[2], 0, changed);
+            return;
+        }
+        let level = if level == 0 {
+            0
+        } else {
+            match self.fed_level(world, pos, fluid) {
+                Some(fed) => {
+                    if fed != level {
+                        world.set_level(pos, fed);
+                        self.schedule_around(world, pos);
+                    }
+                    fed
+                }
+                None => {
+                    self.set(world, pos, BlockKind::AIR, 0, changed);
+                    return;
+                }
+            }
+        };
+        let below = pos.offset(0, -1, 0);
+        if self.can_flow(world, below, fluid, 1) {
+            self.flow(world, below, fluid, 1, changed);
+            return;
+        }
+        // a liquid on top of the same liquid does not spread
+        if level >= fluid.reach() || self.fluid_at(world, below) == Some(fluid) {
+            return;
+        }
+        for [x, y, z] in Self::SIDES {
+            let side = pos.offset(x, y, z);
+            if self.can_flow(world, side, fluid, level + 1) {
+                self.flow(world, side, fluid, level + 1, changed);
+            }
+        }
+    }
+
+    /// Level a flowing block gets from its neighbours, `None` if nothing feeds it
+    fn fed_level(&self, world: &World, pos: BlockPos, fluid: Fluid) -> Option<u8> {
+        if self.fluid_at(world, pos.offset(0, 1, 0)) == Some(fluid) {
+            return Some(1);
+        }
+        Self::SIDES
+            .iter()
+            .filter_map(|&[x, y, z]| match self.get(world, pos.offset(x, y, z)) {
+                Some((side, level)) if side == fluid => Some(level + 1),
+                _ => None,
+            })
+            .min()
+            .filter(|level| *level <= fluid.reach())
+    }
+
+    /// Whether a liquid flowing with `level` replaces the block at `pos`.
+    /// It fills air, raises its own flowing blocks and hardens the other liquid
+    fn can_flow(&self, world: &World, pos: BlockPos, fluid: Fluid, level: u8) -> bool {
+        if pos.split().is_none() {
+            return false;
+        }
+        match world.get_block(pos) {
+            Some(BlockKind::AIR) => true,
+            Some(_) => match self.get(world, pos) {
+                Some((other, current)) if other == fluid => current > level,
+                Some(_) => true,
+                None => false,
+            },
+            None => false,
+        }
+    }
+
+    fn flow(
+        &mut self,
+        world: &mut World,
+        pos: BlockPos,
+        fluid: Fluid,
+        level: u8,
+        changed: &mut Changes,
+    ) {
+        match self.fluid_at(world, pos) {
+            Some(other) if other != fluid => self.set(world, pos, BLOCKS[2], 0, changed),
+            _ => self.set(world, pos, fluid.block(), level, changed),
+        }
+    }
+
+    /// Sets a block and its level, schedules the liquids around it
+    fn set(
+        &mut self,
+        world: &mut World,
+        pos: BlockPos,
+        kind: BlockKind,
+        level: u8,
+        changed: &mut Changes,
+    ) {
+        // a new level of the same liquid looks the same
+        if world.get_block(pos) != Some(kind) {
+            changed.chunks.extend(world.set_block(pos, kind));
+            changed.blocks.insert(pos);
+        }
+        if world.get_level(pos) != level {
+            world.set_level(pos, level);
+        }
+        self.schedule_around(world, pos);
+    }
+
+    /// Schedules the liquid at `pos` and the liquids next to it after their delays
+    fn schedule_around(&mut self, world: &World, pos: BlockPos) {
+        let around = [
+            [0, 0, 0],
+            [0, 1, 0],
+            [0, -1, 0],
+            [1, 0, 0],
+            [-1, 0, 0],
+            [0, 0, 1],
+            [0, 0, -1],
+        ];
+        for [x, y, z] in around {
+            let neighbour = pos.offset(x, y, z);
+            if let Some(fluid) = self.fluid_at(world, neighbour) {
+                self.schedule(neighbour, fluid.delay());
+            }
+        }
+    }
+
+    fn fluid_at(&self, world: &World, pos: BlockPos) -> Option<Fluid> {
+        Fluid::of(world.get_block(pos)?)
+    }
+}
@@ -0,0 +1,121 @@
+//! Voxel raycasting after Amanatides and Woo, "A Fast Voxel Traversal Algorithm for Ray Tracing".
+//!
+//! The ray walks from block to block through the faces it crosses, ordered by the distance
+//! along the ray where it crosses them. No block on the way is skipped or visited twice, and
+//! chunk borders are just block borders.
+use cgmath::{InnerSpace, Vector3};
+
+use crate::block::BlockKind;
+use crate::coords::BlockPos;
+use crate::Position;
+
+use super::World;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RayHit {
+    pub pos: BlockPos,
+    /// Outward normal of the face the ray entered through, zero if the ray starts inside the block
+    pub normal: [i32; 3],
+    /// Distance from the origin along the ray to the hit face
+    pub distance: f32,
+}
+
+impl RayHit {
+    /// The block in front of the hit face, where a block placed on it goes
+    pub fn adjacent(&self) -> BlockPos {
+        let [x, y, z] = self.normal;
+        self.pos.offset(x, y, z)
+    }
+}
+
+/// The first block along a ray that can be targeted, anything but air and liquids,
+/// `None` if there is none within `max_distance`
+pub fn raycast(
+    world: &World,
+    origin: Position,
+    direction: Vector3<f32>,
+    max_distance: f32,
+) -> Option<RayHit> {
+    cast(origin, direction, max_distance, |pos| {
+        world
+            .get_block(pos)
+            .is_some_and(|kind| kind != BlockKind::AIR && !kind.is_liquid())
+    })
+}
+
+/// Walks the blocks along a ray until `is_hit` accepts one, `None` if none within `max_distance`
+/// does. The block containing `origin` is checked first.
+/// `max_distance` has to be finite and not negative, otherwise nothing is hit
+pub fn cast<F>(
+    origin: Position,
+    direction: Vector3<f32>,
+    max_distance: f32,
+    mut is_hit: F,
+) -> Option<RayHit>
+where
+    F: FnMut(BlockPos) -> bool,
+{
+    // the walk ends only past `max_distance`, with NaN or infinity it would never end
+    let length2 = direction.magnitude2();
+    if !(max_distance.is_finite()
+        && max_distance >= 0.0
+        && origin.x.is_finite()
+        && origin.y.is_finite()
+        && origin.z.is_finite()
+        && length2 > 0.0
+        && length2.is_finite())
+    {
+        return None;
+    }
+    let direction = direction.normalize();
+    let start = BlockPos::containing(origin);
+    let mut cell = [start.x, start.y, start.z];
+    let mut step = [0; 3];
+    // distance along the ray to the next face crossing on every axis
+    let mut t_max = [f32::INFINITY; 3];
+    // distance along the ray between two face crossings on every axis
+    let mut t_delta = [f32::INFINITY; 3];
+    for axis in 0..3 {
+        let d = direction[axis];
+        if d > 0.0 {
+            step[axis] = 1;
+            t_max[axis] = (cell[axis] as f32 + 1.0 - origin[axis]) / d;
+            t_delta[axis] = 1.0 / d;
+        } else if d < 0.0 {
+            step[axis] = -1;
+            t_max[axis] = (origin[axis] - cell[axis] as f32) / -d;
+            t_delta[axis] = -1.0 / d;
+        }
+    }
+    let mut normal = [0; 3];
+    let mut distance = 0.0;
+    loop {
+        let pos = BlockPos::from(cell);
+        if is_hit(pos) {
+            return Some(RayHit {
+                pos,
+                normal,
+                distance,
+            });
+        }
+        let axis = if t_max[0] < t_max[1] {
+            if t_max[0] < t_max[2] {
+                0
+            } else {
+                2
+            }
+        } else if t_max[1] < t_max[2] {
+            1
+        } else {
+            2
+        };
+        distance = t_max[axis];
+        if distance > max_distance {
+            return None;
+        }
+        cell[axis] += step[axis];
+        t_max[axis] += t_delta[axis];
+        normal = [0; 3];
+        normal[axis] = -step[axis];
+    }
+}
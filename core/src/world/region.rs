@@ -0,0 +1,225 @@
+//! Region files keep a fixed grid of `Region::SIZE x Region::SIZE` chunks.
+//!
+//! ```text
+//! region:  magic "ALVR" | version u16 | Region::AREA * (offset u32, length u32) | payloads
+//! ```
+//!
+//! An offset table entry with zero length means the chunk was never saved.
+//! A rewritten chunk is appended to the end of the file and its table entry
+//! is updated, the old payload is left in place as garbage.
+use anyhow::*;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use super::save;
+use crate::chunk::storage::ChunkStorage;
+
+pub const MAGIC: [u8; 4] = *b"ALVR";
+pub const FORMAT_VERSION: u16 = 1;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Entry {
+    offset: u32,
+    length: u32,
+}
+
+#[derive(Debug)]
+pub struct Region {
+    file: File,
+    table: Vec<Entry>,
+}
+
+impl Region {
+    /// A region has square base of SIZE*SIZE chunks
+    pub const SIZE: i32 = 32;
+    pub const AREA: usize = (Self::SIZE * Self::SIZE) as usize;
+    const ENTRY_SIZE: u64 = 8;
+    const HEADER_SIZE: u64 = 6;
+    const TABLE_SIZE: u64 = Self::AREA as u64 * Self::ENTRY_SIZE;
+
+    /// Opens a region file, an empty one is created if it does not exist yet
+    pub fn open<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            // an existing region keeps its chunks, its table is updated in place
+            .truncate(false)
+            .open(path.as_ref())?;
+        let mut table = vec![Entry::default(); Self::AREA];
+        if file.metadata()?.len() == 0 {
+            file.write_all(&MAGIC)?;
+            file.write_all(&FORMAT_VERSION.to_le_bytes())?;
+            file.write_all(&vec![0u8; Self::TABLE_SIZE as usize])?;
+        } else {
+            let mut header = vec![0u8; (Self::HEADER_SIZE + Self::TABLE_SIZE) as usize];
+            file.read_exact(&mut header)
+                .with_context(|| format!("region {:?} is truncated", path.as_ref()))?;
+            ensure!(
+                header[0..4] == MAGIC,
+                "{:?} is not a region file",
+                path.as_ref()
+            );
+            let version = u16::from_le_bytes([header[4], header[5]]);
+            ensure!(
+                version == FORMAT_VERSION,
+                "unsupported region format version {}, expected {}",
+                version,
+                FORMAT_VERSION
+            );
+            for (i, entry) in table.iter_mut().enumerate() {
+                let at = Self::HEADER_SIZE as usize + i * Self::ENTRY_SIZE as usize;
+                let bytes = &header[at..at + Self::ENTRY_SIZE as usize];
+                entry.offset = u32::from_le_bytes(bytes[0..4].try_into().unwrap());
+                entry.length = u32::from_le_bytes(bytes[4..8].try_into().unwrap());
+            }
+        }
+        Ok(Region { file, table })
+    }
+
+    /// Region coordinates of a chunk
+    pub fn region_of(x: i32, z: i32) -> (i32, i32) {
+        (x.div_euclid(Self::SIZE), z.div_euclid(Self::SIZE))
+    }
+
+    /// Index of a chunk in the offset table
+    fn entry_index(x: i32, z: i32) -> usize {
+        (x.rem_euclid(Self::SIZE) * Self::SIZE + z.rem_euclid(Self::SIZE)) as usize
+    }
+
+    pub fn contains(&self, x: i32, z: i32) -> bool {
+        self.table[Self::entry_index(x, z)].length > 0
+    }
+
+    /// Local coordinates of all saved chunks in this region
+    pub fn saved_chunks(&self) -> Vec<(i32, i32)> {
+        self.table
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.length > 0)
+            .map(|(i, _)| (i as i32 / Self::SIZE, i as i32 % Self::SIZE))
+            .collect()
+    }
+
+    /// Reads a chunk at world chunk coordinates, `None` if it was never saved
+    pub fn read_chunk(&mut self, x: i32, z: i32) -> Result<Option<ChunkStorage>> {
+        let entry = self.table[Self::entry_index(x, z)];
+        if entry.length == 0 {
+            return Ok(None);
+        }
+        let mut payload = vec![0u8; entry.length as usize];
+        self.file.seek(SeekFrom::Start(entry.offset as u64))?;
+        self.file.read_exact(&mut payload)?;
+        let (saved_x, saved_z, blocks) = save::read_chunk(&mut Cursor::new(payload))?;
+        ensure!(
+            (saved_x, saved_z) == (x, z),
+            "region entry for chunk {}:{} holds chunk {}:{}",
+            x,
+            z,
+            saved_x,
+            saved_z
+        );
+        Ok(Some(blocks))
+    }
+
+    /// Appends a chunk payload and points its table entry to it
+    pub fn write_chunk(&mut self, x: i32, z: i32, blocks: &ChunkStorage) -> Result<()> {
+        let mut payload = Vec::new();
+        save::write_chunk(&mut payload, x, z, blocks)?;
+        let offset = self.file.seek(SeekFrom::End(0))?;
+        ensure!(offset <= u32::MAX as u64, "region file is full");
+        self.file.write_all(&payload)?;
+
+        let i = Self::entry_index(x, z);
+        let entry = Entry {
+            offset: offset as u32,
+            length: payload.len() as u32,
+        };
+        self.file.seek(SeekFrom::Start(
+            Self::HEADER_SIZE + i as u64 * Self::ENTRY_SIZE,
+        ))?;
+        self.file.write_all(&entry.offset.to_le_bytes())?;
+        self.file.write_all(&entry.length.to_le_bytes())?;
+        self.table[i] = entry;
+        Ok(())
+    }
+}
+
+/// Open region files of a world save directory
+#[derive(Debug)]
+pub struct Regions {
+    dir: PathBuf,
+    open: HashMap<(i32, i32), Region>,
+}
+
+impl Regions {
+    pub fn new<P>(dir: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        fs::create_dir_all(dir.as_ref())?;
+        Ok(Regions {
+            dir: dir.as_ref().to_path_buf(),
+            open: HashMap::new(),
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, rx: i32, rz: i32) -> PathBuf {
+        self.dir.join(format!("r.{}.{}.alr", rx, rz))
+    }
+
+    fn region(&mut self, rx: i32, rz: i32) -> Result<&mut Region> {
+        if !self.open.contains_key(&(rx, rz)) {
+            let region = Region::open(self.path(rx, rz))?;
+            self.open.insert((rx, rz), region);
+        }
+        Ok(self.open.get_mut(&(rx, rz)).unwrap())
+    }
+
+    pub fn read_chunk(&mut self, x: i32, z: i32) -> Result<Option<ChunkStorage>> {
+        let (rx, rz) = Region::region_of(x, z);
+        if !self.open.contains_key(&(rx, rz)) && !self.path(rx, rz).exists() {
+            return Ok(None);
+        }
+        self.region(rx, rz)?.read_chunk(x, z)
+    }
+
+    pub fn write_chunk(&mut self, x: i32, z: i32, blocks: &ChunkStorage) -> Result<()> {
+        let (rx, rz) = Region::region_of(x, z);
+        self.region(rx, rz)?.write_chunk(x, z, blocks)
+    }
+
+    /// World chunk coordinates of every chunk saved in the directory
+    pub fn saved_chunks(&mut self) -> Result<Vec<(i32, i32)>> {
+        let mut chunks = vec![];
+        for entry in fs::read_dir(&self.dir)? {
+            let name = entry?.file_name();
+            let name = name.to_string_lossy();
+            let coords: Vec<_> = name.split('.').collect();
+            let (rx, rz) = match coords[..] {
+                ["r", rx, rz, "alr"] => match (rx.parse().ok(), rz.parse().ok()) {
+                    (Some(rx), Some(rz)) => (rx, rz),
+                    _ => continue,
+                },
+                _ => continue,
+            };
+            let region = self.region(rx, rz)?;
+            chunks.extend(
+                region
+                    .saved_chunks()
+                    .into_iter()
+                    .map(|(x, z)| (rx * Region::SIZE + x, rz * Region::SIZE + z)),
+            );
+        }
+        Ok(chunks)
+    }
+}
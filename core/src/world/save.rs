@@ -0,0 +1,177 @@
+//! Binary world save format.
+//!
+//! A world is saved into a directory. `level.dat` keeps the [`Header`] and
+//! the chunks live in region files under `region/`, see [`super::region`].
+//! Every value is stored little-endian.
+//!
+//! ```text
+//! header:  magic "ALVX" | version u16 | seed u64 | chunk_width u16 | chunk_height u16
+//! chunk:   x i32 | z i32 | runs u32 | runs * (length u16, block u16) | levels u32 | levels * (index u16, level u8)
+//! ```
+//!
+//! Chunk blocks are run-length encoded in the same order as `World` keeps
+//! them in memory, which turns the long stretches of air and stone into a
+//! handful of runs. They are followed by the voxels with a non-zero level,
+//! the flowing liquids, see [`ChunkStorage::level`].
+use anyhow::*;
+use std::io::{Read, Write};
+
+use crate::block::{registry, BlockKind};
+use crate::chunk::storage::ChunkStorage;
+
+pub const MAGIC: [u8; 4] = *b"ALVX";
+pub const FORMAT_VERSION: u16 = 3;
+pub const LEVEL_FILE: &str = "level.dat";
+pub const REGION_DIR: &str = "region";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Header {
+    pub version: u16,
+    pub seed: u64,
+    pub chunk_width: u16,
+    pub chunk_height: u16,
+}
+
+impl Header {
+    pub fn write<W: Write>(&self, w: &mut W) -> Result<()> {
+        w.write_all(&MAGIC)?;
+        w.write_all(&self.version.to_le_bytes())?;
+        w.write_all(&self.seed.to_le_bytes())?;
+        w.write_all(&self.chunk_width.to_le_bytes())?;
+        w.write_all(&self.chunk_height.to_le_bytes())?;
+        Ok(())
+    }
+
+    pub fn read<R: Read>(r: &mut R) -> Result<Self> {
+        let mut magic = [0u8; 4];
+        r.read_exact(&mut magic)?;
+        ensure!(
+            magic == MAGIC,
+            "not a world save file, magic is {:?}",
+            magic
+        );
+        let version = read_u16(r)?;
+        ensure!(
+            version == FORMAT_VERSION,
+            "unsupported world format version {}, expected {}",
+            version,
+            FORMAT_VERSION
+        );
+        Ok(Header {
+            version,
+            seed: read_u64(r)?,
+            chunk_width: read_u16(r)?,
+            chunk_height: read_u16(r)?,
+        })
+    }
+}
+
+/// Writes one chunk payload at chunk coordinates `x`, `z`
+pub fn write_chunk<W: Write>(w: &mut W, x: i32, z: i32, blocks: &ChunkStorage) -> Result<()> {
+    let mut runs: Vec<(u16, u16)> = Vec::new();
+    for block in blocks.iter() {
+        let id = block.0;
+        match runs.last_mut() {
+            Some((len, last)) if *last == id && *len < u16::MAX => *len += 1,
+            _ => runs.push((1, id)),
+        }
+    }
+    w.write_all(&x.to_le_bytes())?;
+    w.write_all(&z.to_le_bytes())?;
+    w.write_all(&(runs.len() as u32).to_le_bytes())?;
+    for (len, id) in runs {
+        w.write_all(&len.to_le_bytes())?;
+        w.write_all(&id.to_le_bytes())?;
+    }
+    let levels: Vec<_> = blocks.levels().collect();
+    w.write_all(&(levels.len() as u32).to_le_bytes())?;
+    for (i, level) in levels {
+        w.write_all(&(i as u16).to_le_bytes())?;
+        w.write_all(&[level])?;
+    }
+    Ok(())
+}
+
+/// Reads one chunk payload
+pub fn read_chunk<R: Read>(r: &mut R) -> Result<(i32, i32, ChunkStorage)> {
+    let volume = ChunkStorage::LEN;
+    let x = read_i32(r)?;
+    let z = read_i32(r)?;
+    let runs = read_u32(r)?;
+    let mut blocks = ChunkStorage::default();
+    let mut len_read = 0;
+    for _ in 0..runs {
+        let len = read_u16(r)? as usize;
+        let id = read_u16(r)?;
+        let kind = BlockKind(id);
+        ensure!(
+            registry().get(kind).is_some(),
+            "unknown block id {} in chunk {}:{}",
+            id,
+            x,
+            z
+        );
+        ensure!(
+            len_read + len <= volume,
+            "chunk {}:{} has more than {} blocks",
+            x,
+            z,
+            volume
+        );
+        if kind != BlockKind::AIR {
+            for i in len_read..len_read + len {
+                blocks.set(i, kind);
+            }
+        }
+        len_read += len;
+    }
+    ensure!(
+        len_read == volume,
+        "chunk {}:{} has {} blocks, expected {}",
+        x,
+        z,
+        len_read,
+        volume
+    );
+    let levels = read_u32(r)?;
+    ensure!(
+        levels as usize <= volume,
+        "chunk {}:{} has {} levels, more than its {} blocks",
+        x,
+        z,
+        levels,
+        volume
+    );
+    for _ in 0..levels {
+        let i = read_u16(r)? as usize;
+        let mut level = [0u8; 1];
+        r.read_exact(&mut level)?;
+        ensure!(i < volume, "chunk {}:{} has a level of block {}", x, z, i);
+        blocks.set_level(i, level[0]);
+    }
+    Ok((x, z, blocks))
+}
+
+fn read_u16<R: Read>(r: &mut R) -> Result<u16> {
+    let mut buf = [0u8; 2];
+    r.read_exact(&mut buf)?;
+    Ok(u16::from_le_bytes(buf))
+}
+
+fn read_u32<R: Read>(r: &mut R) -> Result<u32> {
+    let mut buf = [0u8; 4];
+    r.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_i32<R: Read>(r: &mut R) -> Result<i32> {
+    let mut buf = [0u8; 4];
+    r.read_exact(&mut buf)?;
+    Ok(i32::from_le_bytes(buf))
+}
+
+fn read_u64<R: Read>(r: &mut R) -> Result<u64> {
+    let mut buf = [0u8; 8];
+    r.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
@@ -0,0 +1,204 @@
+//! Game time of a [`World`]. The world ticks on a fixed timestep, whatever the frame rate.
+//!
+//! A tick runs the block updates scheduled for it, then random ticks on a few blocks of every
+//! section of every loaded chunk, then the liquids of [`Fluids`]. What an update does to a
+//! block is given by its [`Behaviour`], blocks changed by the liquids wake up the blocks
+//! around them like any other change. Scheduled updates run in block position order and the
+//! random ticks come from a generator seeded with the world seed, so a simulation only
+//! depends on the world and the tick count.
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Duration;
+
+use crate::block::{Behaviour, BlockKind};
+use crate::chunk::Chunk;
+use crate::coords::{BlockPos, ChunkPos, LocalPos};
+
+use super::fluid::Fluids;
+use super::World;
+
+lazy_static! {
+    static ref BLOCKS: [BlockKind; 2] = ["grass", "dirt"].map(BlockKind::named);
+}
+
+#[derive(Debug)]
+pub struct TickLoop {
+    tick: u64,
+    /// Frame time not simulated yet
+    elapsed: Duration,
+    /// Blocks to update by tick, an update is dropped if the block changed kind meanwhile
+    scheduled: BTreeMap<u64, BTreeSet<(BlockPos, BlockKind)>>,
+    rng: ChaCha8Rng,
+    /// Blocks picked in every chunk section on every tick
+    pub random_ticks: u32,
+    pub fluids: Fluids,
+}
+
+impl TickLoop {
+    /// Game time of a tick, 20 ticks per second
+    pub const TICK: Duration = Duration::from_millis(50);
+    /// Most ticks run for a single frame, a longer frame slows the game down
+    pub const MAX_TICKS_PER_FRAME: u32 = 10;
+    pub const RANDOM_TICKS: u32 = 3;
+    /// Ticks a falling block waits before every block it falls
+    pub const FALL_DELAY: u64 = 2;
+
+    pub fn new(seed: u64) -> Self {
+        TickLoop {
+            tick: 0,
+            elapsed: Duration::ZERO,
+            scheduled: BTreeMap::new(),
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            random_ticks: Self::RANDOM_TICKS,
+            fluids: Fluids::new(),
+        }
+    }
+
+    /// Ticks run so far
+    pub fn ticks(&self) -> u64 {
+        self.tick
+    }
+
+    /// Block updates waiting for their tick, liquids excluded
+    pub fn pending(&self) -> usize {
+        self.scheduled.values().map(BTreeSet::len).sum()
+    }
+
+    /// Updates the block at `pos` after `delay` ticks if it is still `kind`, `delay` is at least 1
+    pub fn schedule(&mut self, pos: BlockPos, kind: BlockKind, delay: u64) {
+        self.scheduled
+            .entry(self.tick + delay.max(1))
+            .or_default()
+            .insert((pos, kind));
+    }
+
+    /// Wakes up the blocks around a block changed by something else than the tick loop
+    pub fn block_changed(&mut self, world: &World, pos: BlockPos) {
+        for neighbour in [pos, pos.offset(0, 1, 0)] {
+            if let Some(kind) = world.get_block(neighbour) {
+                if kind.behaviour() == Behaviour::Falling {
+                    self.schedule(neighbour, kind, Self::FALL_DELAY);
+                }
+            }
+        }
+        self.fluids.block_changed(world, pos);
+    }
+
+    /// Runs the ticks due after a frame of `dt`, the rest of the time is kept for the next frame.
+    /// Returns the chunks whose meshes are affected, in order and without repeats
+    pub fn advance(&mut self, world: &mut World, dt: Duration) -> Vec<ChunkPos> {
+        self.elapsed += dt;
+        let mut changed = BTreeSet::new();
+        let mut ticks = 0;
+        while self.elapsed >= Self::TICK {
+            if ticks == Self::MAX_TICKS_PER_FRAME {
+                log::warn!("world ticks are {:?} behind, skipping them", self.elapsed);
+                self.elapsed = Duration::ZERO;
+                break;
+            }
+            self.elapsed -= Self::TICK;
+            changed.extend(self.tick(world));
+            ticks += 1;
+        }
+        changed.into_iter().collect()
+    }
+
+    /// Runs a single tick.
+    /// Returns the chunks whose meshes are affected, in order and without repeats
+    pub fn tick(&mut self, world: &mut World) -> Vec<ChunkPos> {
+        self.tick += 1;
+        let mut changed = BTreeSet::new();
+        if let Some(due) = self.scheduled.remove(&self.tick) {
+            for (pos, kind) in due {
+                if world.get_block(pos) == Some(kind) {
+                    self.scheduled_tick(world, pos, kind, &mut changed);
+                }
+            }
+        }
+        let mut chunks: Vec<_> = world.chunks().keys().copied().collect();
+        chunks.sort();
+        for coords in chunks {
+            for section in 0..Chunk::SECTIONS {
+                for _ in 0..self.random_ticks {
+                    let local = LocalPos::new(
+                        self.rng.gen_range(0..Chunk::WIDTH),
+                        section * Chunk::SECTION_HEIGHT
+                            + self.rng.gen_range(0..Chunk::SECTION_HEIGHT),
+                        self.rng.gen_range(0..Chunk::WIDTH),
+                    );
+                    let pos = coords.block(local);
+                    if let Some(kind) = world.get_block(pos) {
+                        self.random_tick(world, pos, kind, &mut changed);
+                    }
+                }
+            }
+        }
+        let fluids = self.fluids.tick(world);
+        changed.extend(fluids.chunks);
+        for pos in fluids.blocks {
+            self.block_changed(world, pos);
+        }
+        changed.into_iter().collect()
+    }
+
+    fn scheduled_tick(
+        &mut self,
+        world: &mut World,
+        pos: BlockPos,
+        kind: BlockKind,
+        changed: &mut BTreeSet<ChunkPos>,
+    ) {
+        if kind.behaviour() == Behaviour::Falling {
+            let below = pos.offset(0, -1, 0);
+            let free = below.split().is_some()
+                && world.get_block(below).is_some_and(|kind| !kind.is_solid());
+            if free {
+                self.set(world, pos, BlockKind::AIR, changed);
+                self.set(world, below, kind, changed);
+            }
+        }
+    }
+
+    fn random_tick(
+        &mut self,
+        world: &mut World,
+        pos: BlockPos,
+        kind: BlockKind,
+        changed: &mut BTreeSet<ChunkPos>,
+    ) {
+        if kind.behaviour() == Behaviour::Grass {
+            if Self::is_covered(world, pos) {
+                self.set(world, pos, BLOCKS[1], changed);
+                return;
+            }
+            let target = pos.offset(
+                self.rng.gen_range(-1..=1),
+                self.rng.gen_range(-3..=1),
+                self.rng.gen_range(-1..=1),
+            );
+            if world.get_block(target) == Some(BLOCKS[1]) && !Self::is_covered(world, target) {
+                self.set(world, target, BLOCKS[0], changed);
+            }
+        }
+    }
+
+    /// Whether an opaque block or a liquid is on top of `pos`, grass does not grow below them
+    fn is_covered(world: &World, pos: BlockPos) -> bool {
+        world
+            .get_block(pos.offset(0, 1, 0))
+            .is_some_and(|kind| kind.is_opaque() || kind.is_liquid())
+    }
+
+    /// Sets a block and wakes up the blocks around it
+    fn set(
+        &mut self,
+        world: &mut World,
+        pos: BlockPos,
+        kind: BlockKind,
+        changed: &mut BTreeSet<ChunkPos>,
+    ) {
+        changed.extend(world.set_block(pos, kind));
+        self.block_changed(world, pos);
+    }
+}
@@ -0,0 +1,102 @@
+use core::chunk::allocator::{AllocatorStats, RangeAllocator};
+
+#[test]
+fn allocations_do_not_overlap() {
+    let mut allocator = RangeAllocator::new(100);
+    let a = allocator.alloc(30).unwrap();
+    let b = allocator.alloc(50).unwrap();
+    assert_eq!(allocator.get(a), Some(0..30));
+    assert_eq!(allocator.get(b), Some(30..80));
+    assert_eq!(allocator.alloc(30), None);
+    assert!(allocator.alloc(20).is_some());
+    assert_eq!(allocator.stats().free(), 0);
+}
+
+#[test]
+fn freed_space_is_reused() {
+    let mut allocator = RangeAllocator::new(100);
+    let a = allocator.alloc(40).unwrap();
+    allocator.alloc(60).unwrap();
+    assert_eq!(allocator.free(a), Some(0..40));
+    assert_eq!(allocator.free(a), None);
+    let c = allocator.alloc(25).unwrap();
+    assert_eq!(allocator.get(c), Some(0..25));
+    assert_eq!(allocator.get(a), None);
+}
+
+#[test]
+fn free_ranges_are_merged() {
+    let mut allocator = RangeAllocator::new(90);
+    let ids: Vec<_> = (0..3).map(|_| allocator.alloc(30).unwrap()).collect();
+    allocator.free(ids[0]);
+    allocator.free(ids[2]);
+    assert_eq!(allocator.stats().free_ranges, 2);
+    assert_eq!(allocator.alloc(60), None);
+    allocator.free(ids[1]);
+    let stats = allocator.stats();
+    assert_eq!(stats.free_ranges, 1);
+    assert_eq!(stats.largest_free, 90);
+    let id = allocator.alloc(90).unwrap();
+    assert_eq!(allocator.get(id), Some(0..90));
+}
+
+#[test]
+fn compact_packs_allocations_and_keeps_ids() {
+    let mut allocator = RangeAllocator::new(100);
+    let ids: Vec<_> = (0..5).map(|_| allocator.alloc(20).unwrap()).collect();
+    allocator.free(ids[0]);
+    allocator.free(ids[2]);
+    assert_eq!(allocator.alloc(40), None);
+    assert!(allocator.stats().fragmentation() > 0.0);
+
+    let moves = allocator.compact();
+    assert_eq!(moves.len(), 3);
+    let moved: Vec<_> = moves.iter().map(|m| (m.id, m.from, m.to)).collect();
+    assert_eq!(
+        moved,
+        vec![(ids[1], 20, 0), (ids[3], 60, 20), (ids[4], 80, 40)]
+    );
+    assert_eq!(allocator.get(ids[4]), Some(40..60));
+    let stats = allocator.stats();
+    assert_eq!(stats.fragmentation(), 0.0);
+    assert_eq!(stats.largest_free, 40);
+    let id = allocator.alloc(40).unwrap();
+    assert_eq!(allocator.get(id), Some(60..100));
+}
+
+#[test]
+fn grow_adds_free_space_at_the_end() {
+    let mut allocator = RangeAllocator::new(50);
+    let a = allocator.alloc(30).unwrap();
+    allocator.grow(20);
+    assert_eq!(allocator.capacity(), 50);
+    allocator.grow(100);
+    assert_eq!(allocator.stats().free_ranges, 1);
+    let id = allocator.alloc(70).unwrap();
+    assert_eq!(allocator.get(id), Some(30..100));
+    assert_eq!(allocator.get(a), Some(0..30));
+}
+
+#[test]
+fn stats_count_allocations() {
+    let mut allocator = RangeAllocator::new(64);
+    assert_eq!(
+        allocator.stats(),
+        AllocatorStats {
+            capacity: 64,
+            used: 0,
+            allocations: 0,
+            free_ranges: 1,
+            largest_free: 64,
+        }
+    );
+    let a = allocator.alloc(16).unwrap();
+    allocator.alloc(16).unwrap();
+    allocator.free(a);
+    let stats = allocator.stats();
+    assert_eq!(
+        (stats.used, stats.allocations, stats.free_ranges),
+        (16, 1, 2)
+    );
+    assert_eq!(stats.fragmentation(), 1.0 - 32.0 / 48.0);
+}
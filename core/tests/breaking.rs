@@ -0,0 +1,65 @@
+use core::block::BlockKind;
+use core::coords::BlockPos;
+use core::world::breaking::Breaking;
+
+#[test]
+fn hardness_is_the_breaking_time() {
+    let stone = BlockKind::named("stone");
+    assert_eq!(stone.hardness(), 1.5);
+    let pos = BlockPos::new(1, 2, 3);
+    let mut breaking = Breaking::new();
+    let mut frames = 0;
+    while !breaking.update(pos, stone, 0.1) {
+        frames += 1;
+        assert!(frames < 100);
+    }
+    assert_eq!(frames, 14);
+    assert_eq!(breaking.stage(), None);
+}
+
+#[test]
+fn stages_follow_the_progress() {
+    let dirt = BlockKind::named("dirt");
+    let pos = BlockPos::new(0, 20, 0);
+    let mut breaking = Breaking::new();
+    assert_eq!(breaking.stage(), None);
+    let mut stages = Vec::new();
+    while !breaking.update(pos, dirt, 0.05) {
+        stages.push(breaking.stage().unwrap());
+    }
+    assert!(stages.iter().all(|(at, _)| *at == pos));
+    let mut seen: Vec<_> = stages.iter().map(|(_, stage)| *stage).collect();
+    seen.dedup();
+    assert_eq!(seen, (0..Breaking::STAGES).collect::<Vec<_>>());
+}
+
+#[test]
+fn another_target_starts_over() {
+    let stone = BlockKind::named("stone");
+    let mut breaking = Breaking::new();
+    let first = BlockPos::new(0, 20, 0);
+    assert!(!breaking.update(first, stone, 1.4));
+    assert_eq!(breaking.stage(), Some((first, 2)));
+    let second = first.offset(1, 0, 0);
+    assert!(!breaking.update(second, stone, 0.1));
+    assert_eq!(breaking.stage(), Some((second, 0)));
+    // the same place with another block starts over as well
+    assert!(!breaking.update(second, BlockKind::named("oak_log"), 1.9));
+    assert!(breaking.update(second, BlockKind::named("oak_log"), 0.1));
+
+    assert!(!breaking.update(first, stone, 1.4));
+    breaking.stop();
+    assert_eq!(breaking.stage(), None);
+    assert!(!breaking.update(first, stone, 0.2));
+}
+
+#[test]
+fn bedrock_does_not_break() {
+    let bedrock = BlockKind::named("bedrock");
+    let mut breaking = Breaking::new();
+    let pos = BlockPos::new(0, 0, 0);
+    for _ in 0..1000 {
+        assert!(!breaking.update(pos, bedrock, 1.0));
+    }
+    assert_eq!(breaking.stage(), None);
+}
@@ -0,0 +1,99 @@
+//! Fixtures shared by the integration tests
+#![allow(dead_code)]
+use core::block::BlockKind;
+use core::chunk::storage::ChunkStorage;
+use core::chunk::Chunk;
+use core::coords::{BlockPos, ChunkPos, LocalPos};
+use core::world::fluid::Fluids;
+use core::world::tick::TickLoop;
+use core::world::World;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A directory under the system temporary directory, removed when dropped
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    /// `name` keeps the directories of parallel tests apart
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("core-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        TempDir(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// A 3x3 chunk world of generated terrain around the origin
+pub fn small_world(seed: u64) -> World {
+    let mut world = World::empty(seed);
+    for x in -1..=1 {
+        for z in -1..=1 {
+            let coords = ChunkPos::new(x, z);
+            world.insert_chunk(coords, Chunk::generate(seed, coords));
+        }
+    }
+    world
+}
+
+pub fn stone() -> BlockKind {
+    BlockKind::named("stone")
+}
+
+/// Height of the floor of [`flat_world`]
+pub const FLOOR: usize = 10;
+
+/// Chunks from -`radius` to `radius` with a floor of `floor` blocks up to [`FLOOR`], air above it
+pub fn flat_world(floor: &str, radius: i32) -> World {
+    let mut world = World::empty(7);
+    for x in -radius..=radius {
+        for z in -radius..=radius {
+            let mut blocks = ChunkStorage::filled(BlockKind::AIR);
+            for local in LocalPos::all().filter(|local| local.y <= FLOOR) {
+                blocks.set_local(local, BlockKind::named(floor));
+            }
+            world.insert_chunk(ChunkPos::new(x, z), blocks);
+        }
+    }
+    world
+}
+
+/// The block right above the floor of [`flat_world`]
+pub fn surface(x: i32, z: i32) -> BlockPos {
+    BlockPos::new(x, FLOOR as i32 + 1, z)
+}
+
+/// Something updating a world tick by tick
+pub trait Ticking {
+    /// Runs a tick, returns the chunks whose meshes are affected
+    fn tick(&mut self, world: &mut World) -> Vec<ChunkPos>;
+}
+
+impl Ticking for Fluids {
+    fn tick(&mut self, world: &mut World) -> Vec<ChunkPos> {
+        Fluids::tick(self, world).chunks.into_iter().collect()
+    }
+}
+
+impl Ticking for TickLoop {
+    fn tick(&mut self, world: &mut World) -> Vec<ChunkPos> {
+        TickLoop::tick(self, world)
+    }
+}
+
+/// Runs `count` ticks, returns every affected chunk once and in order
+pub fn run<T: Ticking>(ticking: &mut T, world: &mut World, count: u64) -> Vec<ChunkPos> {
+    let mut changed: Vec<_> = (0..count).flat_map(|_| ticking.tick(world)).collect();
+    changed.sort();
+    changed.dedup();
+    changed
+}
@@ -0,0 +1,108 @@
+use core::chunk::Chunk;
+use core::coords::{BlockPos, ChunkPos, LocalPos};
+use core::Position;
+
+const WIDTH: i32 = Chunk::WIDTH as i32;
+const HEIGHT: i32 = Chunk::HEIGHT as i32;
+
+#[test]
+fn local_index_round_trip() {
+    let mut count = 0;
+    for (i, local) in LocalPos::all().enumerate() {
+        assert_eq!(local.index(), i);
+        assert_eq!(LocalPos::from_index(i), local);
+        count += 1;
+    }
+    assert_eq!(count, Chunk::WIDTH * Chunk::HEIGHT * Chunk::WIDTH);
+}
+
+#[test]
+fn local_index_layout() {
+    assert_eq!(LocalPos::new(1, 0, 0).index(), 1);
+    assert_eq!(LocalPos::new(0, 0, 1).index(), Chunk::WIDTH);
+    assert_eq!(LocalPos::new(0, 1, 0).index(), Chunk::WIDTH * Chunk::WIDTH);
+}
+
+#[test]
+fn block_split_round_trip() {
+    for x in -3 * WIDTH..3 * WIDTH {
+        for z in -3 * WIDTH..3 * WIDTH {
+            for y in 0..HEIGHT {
+                let pos = BlockPos::new(x, y, z);
+                let (chunk, local) = pos.split().unwrap();
+                assert_eq!(chunk.block(local), pos);
+                assert_eq!(chunk, pos.chunk());
+                assert_eq!(BlockPos::from(local) + chunk.origin(), pos);
+            }
+        }
+    }
+}
+
+#[test]
+fn chunk_block_round_trip() {
+    for cx in -5..5 {
+        for cz in -5..5 {
+            let chunk = ChunkPos::new(cx, cz);
+            for local in LocalPos::all() {
+                assert_eq!(chunk.block(local).split(), Some((chunk, local)));
+            }
+        }
+    }
+}
+
+#[test]
+fn negative_blocks_use_floor_division() {
+    let split = |x, z| BlockPos::new(x, 0, z).split().unwrap();
+    assert_eq!(
+        split(-1, 0),
+        (ChunkPos::new(-1, 0), LocalPos::new(15, 0, 0))
+    );
+    assert_eq!(
+        split(-16, -17),
+        (ChunkPos::new(-1, -2), LocalPos::new(0, 0, 15))
+    );
+    assert_eq!(
+        split(16, 15),
+        (ChunkPos::new(1, 0), LocalPos::new(0, 0, 15))
+    );
+    assert_eq!(split(0, 0), (ChunkPos::new(0, 0), LocalPos::new(0, 0, 0)));
+}
+
+#[test]
+fn blocks_outside_of_chunk_height() {
+    assert_eq!(BlockPos::new(0, -1, 0).local(), None);
+    assert_eq!(BlockPos::new(0, HEIGHT, 0).split(), None);
+    assert_eq!(BlockPos::new(-1, HEIGHT, 0).chunk(), ChunkPos::new(-1, 0));
+}
+
+#[test]
+fn block_containing_position() {
+    let containing = |x, y, z| BlockPos::containing(Position::new(x, y, z));
+    assert_eq!(containing(0.5, 1.0, 1.99), BlockPos::new(0, 1, 1));
+    assert_eq!(containing(-0.5, 0.0, -1.0), BlockPos::new(-1, 0, -1));
+    assert_eq!(containing(-16.25, 3.5, 16.0), BlockPos::new(-17, 3, 16));
+    for x in -40..40 {
+        let pos = BlockPos::new(x, 5, -x);
+        assert_eq!(BlockPos::containing(pos.position()), pos);
+    }
+}
+
+#[test]
+fn chunk_position() {
+    assert_eq!(
+        ChunkPos::new(-2, 3).position(),
+        Position::new(-32.0, 0.0, 48.0)
+    );
+    assert_eq!(ChunkPos::new(-2, 3).origin(), BlockPos::new(-32, 0, 48));
+}
+
+#[test]
+fn conversions() {
+    let chunk = ChunkPos::from((-7, 9));
+    assert_eq!(chunk, ChunkPos::new(-7, 9));
+    assert_eq!(<(i32, i32)>::from(chunk), (-7, 9));
+    let block = BlockPos::from([3, -4, 5]);
+    assert_eq!(<[i32; 3]>::from(block), [3, -4, 5]);
+    assert_eq!(block - BlockPos::new(1, 1, 1), BlockPos::new(2, -5, 4));
+    assert_eq!(block.offset(-3, 4, -5), BlockPos::default());
+}
@@ -0,0 +1,149 @@
+mod common;
+
+use common::{flat_world, run, stone, surface, TempDir, FLOOR};
+use core::block::BlockKind;
+use core::chunk::Chunk;
+use core::coords::{BlockPos, ChunkPos, LocalPos};
+use core::world::fluid::{Fluid, Fluids};
+use core::world::World;
+
+#[test]
+fn water_spreads_as_far_as_its_reach() {
+    let mut world = flat_world("stone", 1);
+    let mut fluids = Fluids::new();
+    fluids.place(&mut world, surface(8, 8), Fluid::Water);
+    run(&mut fluids, &mut world, 200);
+    assert_eq!(fluids.pending(), 0);
+    assert_eq!(fluids.get(&world, surface(8, 8)), Some((Fluid::Water, 0)));
+    assert_eq!(fluids.get(&world, surface(11, 8)), Some((Fluid::Water, 3)));
+    assert_eq!(fluids.get(&world, surface(10, 6)), Some((Fluid::Water, 4)));
+    assert_eq!(fluids.get(&world, surface(15, 8)), Some((Fluid::Water, 7)));
+    assert_eq!(fluids.get(&world, surface(16, 8)), None);
+    assert_eq!(world.get_block(surface(12, 12)), Some(BlockKind::AIR));
+    assert_eq!(
+        world.get_block(surface(8, 8).offset(0, 1, 0)),
+        Some(BlockKind::AIR)
+    );
+}
+
+#[test]
+fn water_falls_before_it_spreads() {
+    let mut world = flat_world("stone", 1);
+    let mut fluids = Fluids::new();
+    let top = BlockPos::new(8, 20, 8);
+    fluids.place(&mut world, top, Fluid::Water);
+    run(&mut fluids, &mut world, 300);
+    for y in FLOOR as i32 + 1..20 {
+        assert_eq!(
+            fluids.get(&world, BlockPos::new(8, y, 8)),
+            Some((Fluid::Water, 1))
+        );
+    }
+    // the source does not spread in the air
+    assert_eq!(world.get_block(top.offset(1, 0, 0)), Some(BlockKind::AIR));
+    assert_eq!(fluids.get(&world, surface(10, 8)), Some((Fluid::Water, 3)));
+    assert_eq!(fluids.get(&world, surface(8, 14)), Some((Fluid::Water, 7)));
+    assert_eq!(fluids.get(&world, surface(8, 15)), None);
+}
+
+#[test]
+fn water_dries_up_without_a_source() {
+    let mut world = flat_world("stone", 1);
+    let mut fluids = Fluids::new();
+    fluids.place(&mut world, surface(8, 8), Fluid::Water);
+    run(&mut fluids, &mut world, 200);
+    world.set_block(surface(8, 8), BlockKind::AIR);
+    fluids.block_changed(&world, surface(8, 8));
+    run(&mut fluids, &mut world, 400);
+    let water = BlockKind::named("water");
+    let blocks = world.chunk(ChunkPos::new(0, 0)).unwrap();
+    assert!(blocks.iter().all(|kind| kind != water));
+    assert_eq!(fluids.pending(), 0);
+}
+
+#[test]
+fn levels_survive_a_reload() {
+    let dir = TempDir::new("levels_survive_a_reload");
+    let mut world = flat_world("stone", 1);
+    let mut fluids = Fluids::new();
+    fluids.place(&mut world, surface(8, 8), Fluid::Water);
+    run(&mut fluids, &mut world, 200);
+    world.save(dir.path()).unwrap();
+
+    let mut loaded = World::load(dir.path()).unwrap();
+    assert!(loaded.chunks() == world.chunks());
+    assert_eq!(loaded.get_level(surface(11, 8)), 3);
+    // the edge of the flow woken up after the reload does not spread like a source
+    let mut fluids = Fluids::new();
+    fluids.block_changed(&loaded, surface(15, 8));
+    run(&mut fluids, &mut loaded, 200);
+    assert_eq!(fluids.get(&loaded, surface(15, 8)), Some((Fluid::Water, 7)));
+    assert_eq!(loaded.get_block(surface(16, 8)), Some(BlockKind::AIR));
+    assert!(loaded.chunks() == world.chunks());
+}
+
+#[test]
+fn water_flows_across_chunk_borders() {
+    let mut world = flat_world("stone", 1);
+    let mut fluids = Fluids::new();
+    fluids.place(&mut world, surface(1, 8), Fluid::Water);
+    let changed = run(&mut fluids, &mut world, 200);
+    assert_eq!(fluids.get(&world, surface(-3, 8)), Some((Fluid::Water, 4)));
+    assert!(changed.contains(&ChunkPos::new(0, 0)));
+    assert!(changed.contains(&ChunkPos::new(-1, 0)));
+    assert!(!changed.contains(&ChunkPos::new(1, 0)));
+}
+
+#[test]
+fn walls_stop_the_flow() {
+    let mut world = flat_world("stone", 1);
+    for z in 0..Chunk::WIDTH as i32 {
+        world.set_block(surface(10, z), stone());
+    }
+    let mut fluids = Fluids::new();
+    fluids.place(&mut world, surface(8, 8), Fluid::Water);
+    run(&mut fluids, &mut world, 200);
+    assert_eq!(fluids.get(&world, surface(9, 8)), Some((Fluid::Water, 1)));
+    assert_eq!(world.get_block(surface(10, 8)), Some(stone()));
+    assert_eq!(world.get_block(surface(11, 8)), Some(BlockKind::AIR));
+}
+
+#[test]
+fn lava_and_water_make_stone() {
+    let mut world = flat_world("stone", 1);
+    let mut fluids = Fluids::new();
+    fluids.place(&mut world, surface(2, 8), Fluid::Lava);
+    fluids.place(&mut world, surface(12, 8), Fluid::Water);
+    run(&mut fluids, &mut world, 600);
+    assert_eq!(fluids.get(&world, surface(2, 8)), Some((Fluid::Lava, 0)));
+    assert_eq!(fluids.get(&world, surface(12, 8)), Some((Fluid::Water, 0)));
+    assert_eq!(fluids.get(&world, surface(-1, 8)), Some((Fluid::Lava, 3)));
+    let between: Vec<_> = (3..12)
+        .map(|x| world.get_block(surface(x, 8)).unwrap())
+        .collect();
+    assert!(between.contains(&stone()), "{:?}", between);
+}
+
+#[test]
+fn simulation_is_deterministic() {
+    let simulate = |ticks: u64| {
+        let mut world = flat_world("stone", 1);
+        let mut fluids = Fluids::new();
+        fluids.place(&mut world, BlockPos::new(3, 18, 5), Fluid::Water);
+        fluids.place(&mut world, surface(9, 9), Fluid::Lava);
+        fluids.place(&mut world, surface(-4, 2), Fluid::Water);
+        let changed = run(&mut fluids, &mut world, ticks);
+        let blocks: Vec<_> = [ChunkPos::new(-1, 0), ChunkPos::new(0, 0)]
+            .iter()
+            .map(|pos| world.chunk(*pos).unwrap().to_vec())
+            .collect();
+        let levels: Vec<_> = LocalPos::all()
+            .map(|local| fluids.get(&world, local.into()))
+            .collect();
+        (changed, blocks, levels)
+    };
+    for ticks in [17, 64, 250] {
+        assert_eq!(simulate(ticks), simulate(ticks));
+    }
+    assert_ne!(simulate(17).1, simulate(250).1);
+}
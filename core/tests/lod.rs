@@ -0,0 +1,83 @@
+use core::block::BlockKind;
+use core::chunk::lod::{Downsampled, Lod};
+use core::chunk::mesher::Mesher;
+use core::chunk::neighbours::ChunkNeighbours;
+use core::chunk::storage::ChunkStorage;
+use core::chunk::Chunk;
+use core::coords::{ChunkPos, LocalPos};
+
+#[test]
+fn level_goes_up_as_the_distance_doubles() {
+    let levels: Vec<_> = [0, 5, 6, 11, 12, 23, 24, 100]
+        .into_iter()
+        .map(|distance| Lod::for_distance(distance, 6).0)
+        .collect();
+    assert_eq!(levels, vec![0, 0, 1, 1, 2, 2, 3, 3]);
+    // a full distance below one still keeps the chunk of the camera in full
+    assert_eq!(Lod::for_distance(0, 0), Lod::FULL);
+    assert_eq!(Lod::for_distance(1, 0), Lod(1));
+    assert_eq!(Lod::for_distance(i32::MAX, 1), Lod::MAX);
+}
+
+#[test]
+fn downsampled_size_and_bounds() {
+    let stone = BlockKind::named("stone");
+    let voxels = Downsampled::new(&ChunkStorage::filled(stone), Lod(1));
+    assert_eq!(
+        voxels.size,
+        [Chunk::WIDTH / 2, Chunk::HEIGHT / 2, Chunk::WIDTH / 2]
+    );
+    assert_eq!(voxels.get([0, 0, 0]), Some(stone));
+    assert_eq!(voxels.get([7, 31, 7]), Some(stone));
+    assert_eq!(voxels.get([8, 0, 0]), None);
+    assert_eq!(voxels.get([0, -1, 0]), None);
+    assert_eq!(voxels.get([0, 32, 0]), None);
+}
+
+#[test]
+fn full_level_keeps_every_block() {
+    let blocks = Chunk::generate(3, ChunkPos::new(1, 2));
+    let voxels = Downsampled::new(&blocks, Lod::FULL);
+    for local in LocalPos::all() {
+        let pos = [local.x, local.y, local.z].map(|c| c as i32);
+        assert_eq!(voxels.get(pos), Some(blocks.get_local(local)));
+    }
+}
+
+#[test]
+fn voxels_keep_the_top_visible_layer() {
+    let [grass, dirt, stone] = ["grass", "dirt", "stone"].map(BlockKind::named);
+    let mut blocks = ChunkStorage::default();
+    // one voxel of level 2: dirt with a single grass block on top
+    for x in 0..4 {
+        for z in 0..4 {
+            for y in 0..3 {
+                blocks.set_local(LocalPos::new(x, y, z), dirt);
+            }
+        }
+    }
+    blocks.set_local(LocalPos::new(1, 3, 2), grass);
+    // a single stone block fills the voxel next to it
+    blocks.set_local(LocalPos::new(6, 1, 0), stone);
+    let voxels = Downsampled::new(&blocks, Lod(2));
+    assert_eq!(voxels.get([0, 0, 0]), Some(grass));
+    assert_eq!(voxels.get([1, 0, 0]), Some(stone));
+    assert_eq!(voxels.get([0, 1, 0]), Some(BlockKind::AIR));
+}
+
+#[test]
+fn meshes_are_built_at_one_level() {
+    let blocks = Chunk::generate(9, ChunkPos::new(0, 0));
+    let neighbours = ChunkNeighbours::single(&blocks);
+    let full = Mesher::Greedy.build(&neighbours);
+    assert_eq!(full.lod, Lod::FULL);
+    let coarse = Mesher::PerFace.build_at(&neighbours, Lod(2));
+    assert_eq!(coarse.lod, Lod(2));
+    assert!(!coarse.is_empty());
+    assert!(coarse.quads() < full.quads());
+    // vertices stay in block units whatever the level
+    assert!(coarse
+        .vertices
+        .iter()
+        .all(|v| v.position()[1] as usize <= Chunk::HEIGHT && v.position()[0] % 4 == 0));
+}
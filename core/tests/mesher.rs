@@ -0,0 +1,162 @@
+mod common;
+
+use std::collections::HashMap;
+
+use common::small_world;
+use core::block::{BlockKind, RenderType};
+use core::chunk::mesher::{ChunkMesh, Mesher};
+use core::chunk::neighbours::ChunkNeighbours;
+use core::chunk::storage::ChunkStorage;
+use core::chunk::Chunk;
+use core::coords::{BlockPos, ChunkPos, LocalPos};
+use core::vertex::ChunkVertex;
+
+/// A single block face covered by a quad: cell position, face, material,
+/// render type, then ambient occlusion and lowered flag of the corners
+type UnitFace = ([u32; 3], usize, usize, usize, [u8; 4], [bool; 4]);
+
+/// Splits every quad of a mesh into the block faces it covers
+fn unit_faces(mesh: &ChunkMesh) -> Vec<UnitFace> {
+    let mut render_of = HashMap::new();
+    for render in RenderType::ALL {
+        for index in &mesh.indices[render.index()] {
+            render_of.insert(*index as usize / 4, render.index());
+        }
+    }
+    let mut faces = vec![];
+    for (quad_id, quad) in mesh.vertices.chunks_exact(4).enumerate() {
+        let quad: [ChunkVertex; 4] = quad.try_into().unwrap();
+        let positions = quad.iter().map(|v| v.position());
+        let min = positions
+            .clone()
+            .fold([u32::MAX; 3], |a, p| [0, 1, 2].map(|i| a[i].min(p[i])));
+        let max = positions.fold([0; 3], |a, p| [0, 1, 2].map(|i| a[i].max(p[i])));
+        let normal = (0..3).find(|i| min[*i] == max[*i]).unwrap();
+        let (a, b) = match normal {
+            0 => (1, 2),
+            1 => (0, 2),
+            _ => (0, 1),
+        };
+        for ca in min[a]..max[a] {
+            for cb in min[b]..max[b] {
+                let mut cell = min;
+                cell[a] = ca;
+                cell[b] = cb;
+                faces.push((
+                    cell,
+                    quad[0].face(),
+                    quad[0].material(),
+                    render_of[&quad_id],
+                    quad.map(|v| v.ao()),
+                    quad.map(|v| v.lowered()),
+                ));
+            }
+        }
+    }
+    faces.sort();
+    faces
+}
+
+fn assert_same_surface(neighbours: &ChunkNeighbours) {
+    let per_face = Mesher::PerFace.build(neighbours);
+    let greedy = Mesher::Greedy.build(neighbours);
+    assert!(greedy.quads() <= per_face.quads());
+    let per_face = unit_faces(&per_face);
+    assert!(!per_face.is_empty());
+    assert!(per_face == unit_faces(&greedy));
+}
+
+#[test]
+fn greedy_matches_per_face_on_generated_chunks() {
+    let world = small_world(5);
+    for x in -1..=1 {
+        for z in -1..=1 {
+            assert_same_surface(&ChunkNeighbours::new(&world, ChunkPos::new(x, z)));
+        }
+    }
+}
+
+#[test]
+fn greedy_matches_per_face_without_neighbours() {
+    let blocks = Chunk::generate(11, ChunkPos::new(2, -3));
+    assert_same_surface(&ChunkNeighbours::single(&blocks));
+}
+
+#[test]
+fn greedy_matches_per_face_on_mixed_blocks() {
+    let [stone, glass, water, dirt] = ["stone", "glass", "water", "dirt"].map(BlockKind::named);
+    let mut blocks = ChunkStorage::default();
+    for local in LocalPos::all() {
+        let kind = match (local.x + 2 * local.z + 3 * local.y) % 7 {
+            _ if local.y > 12 => BlockKind::AIR,
+            0 | 1 => stone,
+            2 => glass,
+            3 | 4 => water,
+            5 => dirt,
+            _ => BlockKind::AIR,
+        };
+        blocks.set_local(local, kind);
+    }
+    assert_same_surface(&ChunkNeighbours::single(&blocks));
+}
+
+#[test]
+fn greedy_merges_flat_ground() {
+    let blocks = ChunkStorage::filled(BlockKind::named("stone"));
+    let neighbours = ChunkNeighbours::single(&blocks);
+    let greedy = Mesher::Greedy.build(&neighbours);
+    // one quad per chunk side
+    assert_eq!(greedy.quads(), 6);
+    assert!(unit_faces(&greedy) == unit_faces(&Mesher::PerFace.build(&neighbours)));
+}
+
+#[test]
+fn liquid_sides_are_lowered_only_below_open_space() {
+    let [water, stone] = ["water", "stone"].map(BlockKind::named);
+    let mut blocks = ChunkStorage::default();
+    // a pool of two water blocks, one of them under a stone block
+    blocks.set_local(LocalPos::new(4, 5, 4), water);
+    blocks.set_local(LocalPos::new(4, 6, 4), stone);
+    blocks.set_local(LocalPos::new(8, 5, 8), water);
+    let mesh = Mesher::PerFace.build(&ChunkNeighbours::single(&blocks));
+    let water_faces: Vec<_> = unit_faces(&mesh)
+        .into_iter()
+        .filter(|face| face.3 == RenderType::Water.index())
+        .collect();
+    let lowered = |x: u32| {
+        water_faces
+            .iter()
+            .filter(|face| face.0[0] == x || face.0[0] == x + 1)
+            .any(|face| face.5.contains(&true))
+    };
+    assert!(!water_faces.is_empty());
+    assert!(!lowered(4));
+    assert!(lowered(8));
+}
+
+#[test]
+fn block_changes_remesh_the_chunks_that_see_them() {
+    let mut world = small_world(5);
+    let dirt = BlockKind::named("dirt");
+    let origin = ChunkPos::new(0, 0);
+    assert_eq!(world.set_block(BlockPos::new(5, 20, 5), dirt), vec![origin]);
+    assert_eq!(
+        world.set_block(BlockPos::new(0, 20, 5), dirt),
+        vec![origin, ChunkPos::new(-1, 0)]
+    );
+    // the diagonal chunk reads the corner column for its ambient occlusion
+    assert_eq!(
+        world.set_block(BlockPos::new(15, 20, 0), dirt),
+        vec![
+            origin,
+            ChunkPos::new(1, 0),
+            ChunkPos::new(0, -1),
+            ChunkPos::new(1, -1)
+        ]
+    );
+    // neighbours which are not loaded are left out
+    assert_eq!(
+        world.set_block(BlockPos::new(31, 20, 31), dirt),
+        vec![ChunkPos::new(1, 1)]
+    );
+}
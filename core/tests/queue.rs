@@ -0,0 +1,147 @@
+use cgmath::Vector2;
+
+use core::chunk::manager::{ChunkManager, LoadTicket, Tickets};
+use core::chunk::queue::{LoadQueue, Viewer, Work};
+use core::chunk::worker::JobHandle;
+use core::chunk::Chunk;
+use core::coords::ChunkPos;
+
+const WIDTH: f32 = Chunk::WIDTH as f32;
+
+/// A viewer in the middle of chunk `pos` looking along `direction`
+fn viewer(pos: ChunkPos, direction: (f32, f32)) -> Viewer {
+    Viewer::new(
+        Vector2::new(pos.x as f32 + 0.5, pos.z as f32 + 0.5) * WIDTH,
+        direction.into(),
+    )
+}
+
+fn drain(queue: &mut LoadQueue) -> Vec<ChunkPos> {
+    std::iter::from_fn(|| queue.pop().map(|queued| queued.pos)).collect()
+}
+
+#[test]
+fn nearest_chunks_come_first() {
+    let viewer = viewer(ChunkPos::new(0, 0), (0.0, 0.0));
+    let mut queue = LoadQueue::new(viewer);
+    for x in [5, -1, 3, 0, -4, 2] {
+        queue.push(ChunkPos::new(x, 0), Work::Generate, JobHandle::default());
+    }
+    let order: Vec<_> = drain(&mut queue).iter().map(|pos| pos.x).collect();
+    assert_eq!(order, vec![0, -1, 2, 3, -4, 5]);
+}
+
+#[test]
+fn chunks_in_front_come_before_chunks_behind() {
+    let viewer = viewer(ChunkPos::new(0, 0), (1.0, 0.0));
+    let ahead = viewer.priority(ChunkPos::new(4, 0));
+    let side = viewer.priority(ChunkPos::new(0, 4));
+    let behind = viewer.priority(ChunkPos::new(-4, 0));
+    assert!(ahead < side && side < behind);
+    // a chunk behind has twice the priority of the same chunk ahead
+    assert!((behind - 2.0 * ahead).abs() < 1e-4);
+    // but a much closer chunk behind still comes before a far one ahead
+    assert!(viewer.priority(ChunkPos::new(-2, 0)) < viewer.priority(ChunkPos::new(8, 0)));
+}
+
+#[test]
+fn neighbour_chunks_ignore_direction() {
+    let viewer = viewer(ChunkPos::new(0, 0), (0.0, 1.0));
+    assert_eq!(viewer.priority(ChunkPos::new(0, 0)), 0.0);
+    assert_eq!(
+        viewer.priority(ChunkPos::new(0, -1)),
+        viewer.priority(ChunkPos::new(0, 1))
+    );
+}
+
+#[test]
+fn turning_reorders_the_queue() {
+    let mut queue = LoadQueue::new(viewer(ChunkPos::new(0, 0), (1.0, 0.0)));
+    for pos in [ChunkPos::new(5, 0), ChunkPos::new(-5, 0)] {
+        queue.push(pos, Work::Generate, JobHandle::default());
+    }
+    queue.set_viewer(viewer(ChunkPos::new(0, 0), (-1.0, 0.0)));
+    assert_eq!(
+        drain(&mut queue),
+        vec![ChunkPos::new(-5, 0), ChunkPos::new(5, 0)]
+    );
+}
+
+#[test]
+fn moving_reorders_the_queue() {
+    let mut queue = LoadQueue::new(viewer(ChunkPos::new(0, 0), (0.0, 0.0)));
+    for pos in [ChunkPos::new(2, 0), ChunkPos::new(10, 0)] {
+        queue.push(pos, Work::Generate, JobHandle::default());
+    }
+    queue.set_viewer(viewer(ChunkPos::new(10, 0), (0.0, 0.0)));
+    assert_eq!(
+        drain(&mut queue),
+        vec![ChunkPos::new(10, 0), ChunkPos::new(2, 0)]
+    );
+}
+
+#[test]
+fn small_turns_keep_the_order() {
+    let first = viewer(ChunkPos::new(0, 0), (1.0, 0.0));
+    assert!(!first.differs(&viewer(ChunkPos::new(0, 0), (1.0, 0.05))));
+    assert!(first.differs(&viewer(ChunkPos::new(0, 0), (1.0, 1.0))));
+    assert!(first.differs(&viewer(ChunkPos::new(1, 0), (1.0, 0.0))));
+}
+
+#[test]
+fn cancelled_work_is_skipped() {
+    let mut queue = LoadQueue::new(viewer(ChunkPos::new(0, 0), (0.0, 0.0)));
+    let cancelled = JobHandle::default();
+    queue.push(ChunkPos::new(0, 0), Work::Generate, cancelled.clone());
+    queue.push(ChunkPos::new(3, 0), Work::Generate, JobHandle::default());
+    cancelled.cancel();
+    assert_eq!(queue.len(), 2);
+    assert_eq!(drain(&mut queue), vec![ChunkPos::new(3, 0)]);
+
+    let cancelled = JobHandle::default();
+    queue.push(ChunkPos::new(1, 0), Work::Generate, cancelled.clone());
+    cancelled.cancel();
+    queue.set_viewer(viewer(ChunkPos::new(6, 0), (0.0, 0.0)));
+    assert!(queue.is_empty());
+}
+
+#[test]
+fn tickets_pin_their_chunks() {
+    let mut tickets = Tickets::default();
+    let spawn = tickets.add(LoadTicket::new(ChunkPos::new(0, 0), 2));
+    let machine = tickets.add(LoadTicket::new(ChunkPos::new(3, 0), 1));
+    assert_ne!(spawn, machine);
+    assert!(tickets.pins(ChunkPos::new(-2, 2)));
+    assert!(tickets.pins(ChunkPos::new(4, -1)));
+    assert!(!tickets.pins(ChunkPos::new(-3, 0)));
+    // 5x5 and 3x3 chunks, two of them overlap
+    assert_eq!(tickets.chunks().count(), 25 + 9);
+
+    // a chunk of both tickets stays pinned until both are removed
+    let shared = ChunkPos::new(2, 0);
+    assert_eq!(
+        tickets.remove(spawn),
+        Some(LoadTicket::new(ChunkPos::new(0, 0), 2))
+    );
+    assert!(tickets.pins(shared));
+    assert!(!tickets.pins(ChunkPos::new(0, 0)));
+    assert_eq!(tickets.remove(spawn), None);
+    tickets.remove(machine);
+    assert!(!tickets.pins(shared));
+    assert!(tickets.is_empty());
+}
+
+#[test]
+fn pinned_blocks_outlive_the_render_distance() {
+    let mut tickets = Tickets::default();
+    let id = tickets.add(LoadTicket::new(ChunkPos::new(0, 0), 1));
+    let far = ChunkPos::new(20, 0);
+    let keeps = |tickets: &Tickets, pos| ChunkManager::keeps_blocks(far, 4, tickets, pos);
+    // the render distance and one more ring of neighbours
+    assert!(keeps(&tickets, ChunkPos::new(25, 5)));
+    assert!(!keeps(&tickets, ChunkPos::new(26, 0)));
+    assert!(keeps(&tickets, ChunkPos::new(1, 1)));
+    assert!(!keeps(&tickets, ChunkPos::new(2, 0)));
+    tickets.remove(id);
+    assert!(!keeps(&tickets, ChunkPos::new(1, 1)));
+}
@@ -0,0 +1,158 @@
+mod common;
+
+use cgmath::{vec3, InnerSpace};
+use common::stone;
+use core::block::BlockKind;
+use core::chunk::storage::ChunkStorage;
+use core::coords::{BlockPos, ChunkPos};
+use core::world::raycast::{cast, raycast, RayHit};
+use core::world::World;
+
+/// Empty chunks from -1 to 1
+fn empty_world() -> World {
+    let mut world = World::empty(7);
+    for x in -1..=1 {
+        for z in -1..=1 {
+            world.insert_chunk(ChunkPos::new(x, z), ChunkStorage::filled(BlockKind::AIR));
+        }
+    }
+    world
+}
+
+fn assert_hit(hit: Option<RayHit>, pos: BlockPos, normal: [i32; 3], distance: f32) {
+    let hit = hit.expect("the ray misses");
+    assert_eq!(hit.pos, pos);
+    assert_eq!(hit.normal, normal);
+    assert!(
+        (hit.distance - distance).abs() < 1e-4,
+        "distance {} instead of {}",
+        hit.distance,
+        distance
+    );
+}
+
+#[test]
+fn hits_the_faces_along_every_axis() {
+    let mut world = empty_world();
+    let target = BlockPos::new(5, 20, 5);
+    world.set_block(target, stone());
+    let center = vec3(5.5, 20.5, 5.5);
+    let cases = [
+        (vec3(1.0, 0.0, 0.0), [1, 0, 0]),
+        (vec3(-1.0, 0.0, 0.0), [-1, 0, 0]),
+        (vec3(0.0, 1.0, 0.0), [0, 1, 0]),
+        (vec3(0.0, -1.0, 0.0), [0, -1, 0]),
+        (vec3(0.0, 0.0, 1.0), [0, 0, 1]),
+        (vec3(0.0, 0.0, -1.0), [0, 0, -1]),
+    ];
+    for (side, normal) in cases {
+        let origin = center + side * 3.0;
+        let hit = raycast(&world, origin, -side, 10.0);
+        assert_hit(hit, target, normal, 2.5);
+        assert_eq!(
+            hit.unwrap().adjacent(),
+            target.offset(normal[0], normal[1], normal[2])
+        );
+    }
+}
+
+#[test]
+fn diagonal_rays_cross_chunk_borders() {
+    let mut world = empty_world();
+    let target = BlockPos::new(-3, 12, -2);
+    world.set_block(target, stone());
+    let origin = vec3(4.5, 19.5, 4.5);
+    let direction = target.position() + vec3(0.5, 1.0, 0.5) - origin;
+    let hit = raycast(&world, origin, direction, 20.0);
+    assert_hit(hit, target, [0, 1, 0], direction.magnitude());
+}
+
+#[test]
+fn negative_directions_enter_through_the_near_face() {
+    let mut world = empty_world();
+    world.set_block(BlockPos::new(-15, 30, -1), stone());
+    let hit = raycast(&world, vec3(1.25, 30.5, -0.5), vec3(-1.0, 0.0, 0.0), 32.0);
+    assert_hit(hit, BlockPos::new(-15, 30, -1), [1, 0, 0], 15.25);
+}
+
+#[test]
+fn nothing_is_hit_beyond_the_range() {
+    let mut world = empty_world();
+    world.set_block(BlockPos::new(10, 20, 0), stone());
+    let origin = vec3(0.5, 20.5, 0.5);
+    assert!(raycast(&world, origin, vec3(1.0, 0.0, 0.0), 9.0).is_none());
+    assert_hit(
+        raycast(&world, origin, vec3(1.0, 0.0, 0.0), 9.5),
+        BlockPos::new(10, 20, 0),
+        [-1, 0, 0],
+        9.5,
+    );
+    // the ray leaves the world
+    assert!(raycast(&world, origin, vec3(0.0, 1.0, 0.0), 500.0).is_none());
+    assert!(raycast(&world, origin, vec3(0.0, 0.0, 0.0), 10.0).is_none());
+}
+
+#[test]
+fn unbounded_distances_hit_nothing() {
+    let world = empty_world();
+    let origin = vec3(0.5, 20.5, 0.5);
+    for max_distance in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY, -1.0] {
+        assert!(raycast(&world, origin, vec3(1.0, 0.2, 0.0), max_distance).is_none());
+    }
+    let nan = vec3(f32::NAN, 20.5, 0.5);
+    assert!(raycast(&world, nan, vec3(1.0, 0.0, 0.0), 10.0).is_none());
+    // even when the block of the origin would be hit
+    assert!(cast(origin, vec3(1.0, 0.0, 0.0), f32::NAN, |_| true).is_none());
+}
+
+#[test]
+fn first_block_wins() {
+    let mut world = empty_world();
+    for x in 3..8 {
+        world.set_block(BlockPos::new(x, 20, 0), stone());
+    }
+    let hit = raycast(&world, vec3(0.5, 20.5, 0.5), vec3(1.0, 0.0, 0.0), 20.0);
+    assert_hit(hit, BlockPos::new(3, 20, 0), [-1, 0, 0], 2.5);
+    let hit = raycast(&world, vec3(9.5, 20.5, 0.5), vec3(-1.0, 0.0, 0.0), 20.0);
+    assert_hit(hit, BlockPos::new(7, 20, 0), [1, 0, 0], 1.5);
+}
+
+#[test]
+fn starting_inside_a_block_hits_it() {
+    let mut world = empty_world();
+    world.set_block(BlockPos::new(2, 20, 2), stone());
+    let hit = raycast(&world, vec3(2.3, 20.7, 2.1), vec3(0.3, -1.0, 0.2), 5.0);
+    assert_hit(hit, BlockPos::new(2, 20, 2), [0, 0, 0], 0.0);
+}
+
+#[test]
+fn liquids_and_air_are_not_targets() {
+    let mut world = empty_world();
+    let water = BlockKind::named("water");
+    for y in 10..20 {
+        world.set_block(BlockPos::new(0, y, 0), water);
+    }
+    world.set_block(BlockPos::new(0, 9, 0), stone());
+    let hit = raycast(&world, vec3(0.5, 25.0, 0.5), vec3(0.0, -1.0, 0.0), 20.0);
+    assert_hit(hit, BlockPos::new(0, 9, 0), [0, 1, 0], 15.0);
+}
+
+#[test]
+fn visits_every_block_on_the_way_once() {
+    let origin = vec3(0.2, 0.7, 0.4);
+    let direction = vec3(3.0, -1.3, 2.1);
+    let mut visited = Vec::new();
+    cast(origin, direction, 10.0, |pos| {
+        visited.push(pos);
+        false
+    });
+    assert_eq!(visited[0], BlockPos::new(0, 0, 0));
+    for pair in visited.windows(2) {
+        let d = pair[1] - pair[0];
+        // neighbours share a face
+        assert_eq!(d.x.abs() + d.y.abs() + d.z.abs(), 1, "{:?}", pair);
+    }
+    let last = visited.last().unwrap().position() + vec3(0.5, 0.5, 0.5);
+    let end = origin + direction.normalize() * 10.0;
+    assert!((last - end).magnitude() < 1.8);
+}
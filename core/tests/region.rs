@@ -0,0 +1,95 @@
+mod common;
+
+use common::{small_world, TempDir};
+use core::block::BlockKind;
+use core::chunk::storage::ChunkStorage;
+use core::coords::{ChunkPos, LocalPos};
+use core::world::region::{Region, Regions};
+use core::world::World;
+
+#[test]
+fn region_of_negative_chunks() {
+    assert_eq!(Region::region_of(0, 0), (0, 0));
+    assert_eq!(Region::region_of(Region::SIZE - 1, 0), (0, 0));
+    assert_eq!(Region::region_of(Region::SIZE, -1), (1, -1));
+    assert_eq!(
+        Region::region_of(-Region::SIZE, -Region::SIZE - 1),
+        (-1, -2)
+    );
+}
+
+#[test]
+fn chunks_round_trip_across_regions() {
+    let dir = TempDir::new("chunks_round_trip_across_regions");
+    let mut regions = Regions::new(dir.path()).unwrap();
+    let stone = ChunkStorage::filled(BlockKind::named("stone"));
+    let mut mixed = ChunkStorage::default();
+    mixed.set_local(LocalPos::new(1, 2, 3), BlockKind::named("dirt"));
+    regions.write_chunk(-1, 0, &stone).unwrap();
+    regions.write_chunk(Region::SIZE + 2, 5, &mixed).unwrap();
+
+    let mut reopened = Regions::new(dir.path()).unwrap();
+    assert!(reopened.read_chunk(-1, 0).unwrap().unwrap() == stone);
+    assert!(reopened.read_chunk(Region::SIZE + 2, 5).unwrap().unwrap() == mixed);
+    assert!(reopened.read_chunk(0, 0).unwrap().is_none());
+    assert!(reopened.read_chunk(-100, 40).unwrap().is_none());
+    let mut saved = reopened.saved_chunks().unwrap();
+    saved.sort();
+    assert_eq!(saved, vec![(-1, 0), (Region::SIZE + 2, 5)]);
+}
+
+#[test]
+fn rewritten_chunk_reads_latest() {
+    let dir = TempDir::new("rewritten_chunk_reads_latest");
+    let mut regions = Regions::new(dir.path()).unwrap();
+    regions
+        .write_chunk(3, 4, &ChunkStorage::filled(BlockKind::named("stone")))
+        .unwrap();
+    regions
+        .write_chunk(3, 4, &ChunkStorage::filled(BlockKind::named("dirt")))
+        .unwrap();
+    let blocks = regions.read_chunk(3, 4).unwrap().unwrap();
+    assert!(blocks == ChunkStorage::filled(BlockKind::named("dirt")));
+}
+
+#[test]
+fn single_chunk_save_and_load() {
+    let dir = TempDir::new("single_chunk_save_and_load");
+    let mut world = small_world(7);
+    world.save(dir.path()).unwrap();
+
+    let coords = ChunkPos::new(1, -1);
+    let chunk = world.chunk_mut(coords).unwrap();
+    chunk.set_local(LocalPos::new(0, 0, 0), BlockKind::AIR);
+    let chunk = chunk.clone();
+    world.save_chunk(coords).unwrap();
+
+    let mut opened = World::open(dir.path()).unwrap();
+    assert!(opened.chunks().is_empty());
+    assert!(opened.load_chunk(coords).unwrap());
+    assert!(opened.chunk(coords).unwrap() == &chunk);
+    assert_eq!(opened.chunks().len(), 1);
+    assert!(!opened.load_chunk(ChunkPos::new(-40, 12)).unwrap());
+}
+
+#[test]
+fn unload_writes_changed_chunk() {
+    let dir = TempDir::new("unload_writes_changed_chunk");
+    let mut world = small_world(7);
+    world.save(dir.path()).unwrap();
+
+    let coords = ChunkPos::new(0, 0);
+    world
+        .chunk_mut(coords)
+        .unwrap()
+        .set_local(LocalPos::new(5, 5, 5), BlockKind::named("dirt"));
+    world.unload_chunk(coords).unwrap();
+    assert!(world.chunk(coords).is_none());
+
+    assert!(world.load_chunk(coords).unwrap());
+    let local = LocalPos::new(5, 5, 5);
+    assert_eq!(
+        world.chunk(coords).unwrap().get_local(local),
+        BlockKind::named("dirt")
+    );
+}
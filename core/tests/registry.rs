@@ -0,0 +1,149 @@
+mod common;
+
+use common::TempDir;
+use core::block::{registry, BlockKind, BlockRegistry, RenderType};
+
+const AIR: &str = r#"{ "id": 0, "name": "air", "opaque": false, "solid": false }"#;
+
+fn from_defs(defs: &[&str]) -> anyhow::Result<BlockRegistry> {
+    BlockRegistry::from_json(&format!("[{}]", defs.join(",")))
+}
+
+/// Texture names of the faces of a block in `Voxel::FACES` order
+fn face_textures(registry: &BlockRegistry, name: &str) -> [String; 6] {
+    let kind = registry.kind(name).unwrap();
+    [0, 1, 2, 3, 4, 5]
+        .map(|face_id| registry.textures()[registry.face_layer(kind, face_id)].clone())
+}
+
+#[test]
+fn duplicate_id_is_rejected() {
+    let error = from_defs(&[
+        AIR,
+        r#"{ "id": 1, "name": "stone", "textures": { "all": "stone" } }"#,
+        r#"{ "id": 1, "name": "dirt", "textures": { "all": "dirt" } }"#,
+    ])
+    .unwrap_err();
+    assert!(error.to_string().contains("id 1"), "{}", error);
+}
+
+#[test]
+fn duplicate_name_is_rejected() {
+    let error = from_defs(&[
+        AIR,
+        r#"{ "id": 1, "name": "stone", "textures": { "all": "stone" } }"#,
+        r#"{ "id": 2, "name": "stone", "textures": { "all": "stone" } }"#,
+    ])
+    .unwrap_err();
+    assert!(error.to_string().contains("stone"), "{}", error);
+}
+
+#[test]
+fn id_zero_has_to_be_air() {
+    let missing = from_defs(&[r#"{ "id": 1, "name": "stone", "textures": { "all": "stone" } }"#]);
+    assert!(missing.is_err());
+    let visible = from_defs(&[
+        r#"{ "id": 0, "name": "air", "textures": { "all": "stone" }, "opaque": false, "solid": false }"#,
+    ]);
+    assert!(visible.is_err());
+    let solid = from_defs(&[r#"{ "id": 0, "name": "air", "opaque": false }"#]);
+    assert!(solid.is_err());
+    assert!(from_defs(&[AIR]).is_ok());
+}
+
+#[test]
+fn transparent_render_types_can_not_be_opaque() {
+    let error = from_defs(&[
+        AIR,
+        r#"{ "id": 1, "name": "glass", "textures": { "all": "glass" }, "render": "cutout" }"#,
+    ])
+    .unwrap_err();
+    assert!(error.to_string().contains("glass"), "{}", error);
+}
+
+#[test]
+fn face_textures_resolve_by_precedence() {
+    let registry = from_defs(&[
+        AIR,
+        r#"{ "id": 1, "name": "grass", "textures": { "all": "dirt", "top": "grass_top", "side": "grass_side" } }"#,
+        r#"{ "id": 2, "name": "furnace", "textures": { "all": "stone", "front": "furnace_front" } }"#,
+        r#"{ "id": 3, "name": "log", "textures": { "side": "log", "top": "log_top", "bottom": "log_top" } }"#,
+    ])
+    .unwrap();
+    // back, front, top, bottom, left, right
+    assert_eq!(
+        face_textures(&registry, "grass"),
+        [
+            "grass_side",
+            "grass_side",
+            "grass_top",
+            "dirt",
+            "grass_side",
+            "grass_side"
+        ]
+        .map(String::from)
+    );
+    assert_eq!(
+        face_textures(&registry, "furnace"),
+        ["stone", "furnace_front", "stone", "stone", "stone", "stone"].map(String::from)
+    );
+    assert_eq!(
+        face_textures(&registry, "log"),
+        ["log", "log", "log_top", "log_top", "log", "log"].map(String::from)
+    );
+}
+
+#[test]
+fn textures_share_layers() {
+    let registry = from_defs(&[
+        AIR,
+        r#"{ "id": 1, "name": "grass", "textures": { "top": "grass_top", "side": "dirt", "bottom": "dirt" } }"#,
+        r#"{ "id": 2, "name": "dirt", "textures": { "all": "dirt" } }"#,
+    ])
+    .unwrap();
+    assert_eq!(registry.textures(), ["dirt", "grass_top"].map(String::from));
+    let [grass, dirt] = [BlockKind(1), BlockKind(2)];
+    assert_eq!(registry.face_layer(grass, 3), registry.face_layer(dirt, 0));
+}
+
+#[test]
+fn missing_face_texture_is_rejected() {
+    let error = from_defs(&[
+        AIR,
+        r#"{ "id": 1, "name": "log", "textures": { "side": "log" } }"#,
+    ])
+    .unwrap_err();
+    assert!(error.to_string().contains("log"), "{}", error);
+}
+
+#[test]
+fn unknown_field_is_rejected() {
+    assert!(from_defs(&[AIR, r#"{ "id": 1, "name": "stone", "colour": "grey" }"#]).is_err());
+}
+
+#[test]
+fn load_from_file() {
+    let dir = TempDir::new("registry_load_from_file");
+    let path = dir.path().join("blocks.json");
+    std::fs::write(
+        &path,
+        format!(
+            "[{}, {}]",
+            AIR, r#"{ "id": 7, "name": "ruby", "textures": { "all": "ruby" }, "render": "translucent", "opaque": false }"#
+        ),
+    )
+    .unwrap();
+    let registry = BlockRegistry::load(&path).unwrap();
+    assert_eq!(registry.len(), 8);
+    assert!(!registry.is_empty());
+    assert_eq!(registry.kind("ruby"), Some(BlockKind(7)));
+    assert_eq!(registry.render_type(BlockKind(7)), RenderType::Translucent);
+    assert!(registry.get(BlockKind(3)).is_none());
+    assert!(BlockRegistry::load(dir.path().join("missing.json")).is_err());
+}
+
+#[test]
+fn resource_registry_is_valid() {
+    assert_eq!(registry().get(BlockKind::AIR).unwrap().name, "air");
+    assert!(registry().kind("stone").is_some());
+}
@@ -0,0 +1,89 @@
+mod common;
+
+use common::{small_world, TempDir};
+use core::block::BlockKind;
+use core::coords::{ChunkPos, LocalPos};
+use core::world::save::{self, Header};
+use core::world::World;
+use std::io::Cursor;
+
+fn header(version: u16) -> Header {
+    Header {
+        version,
+        seed: 42,
+        chunk_width: World::CHUNK_WIDTH as u16,
+        chunk_height: World::CHUNK_HEIGHT as u16,
+    }
+}
+
+#[test]
+fn world_round_trip() {
+    let dir = TempDir::new("world_round_trip");
+    let mut world = small_world(13);
+    world.save(dir.path()).unwrap();
+    let loaded = World::load(dir.path()).unwrap();
+    assert_eq!(loaded.seed(), world.seed());
+    assert_eq!(loaded.chunks().len(), 9);
+    assert!(loaded.chunks() == world.chunks());
+}
+
+#[test]
+fn header_round_trip() {
+    let mut bytes = Vec::new();
+    header(save::FORMAT_VERSION).write(&mut bytes).unwrap();
+    let read = Header::read(&mut Cursor::new(bytes)).unwrap();
+    assert_eq!(read, header(save::FORMAT_VERSION));
+}
+
+#[test]
+fn bad_magic_is_rejected() {
+    let mut bytes = Vec::new();
+    header(save::FORMAT_VERSION).write(&mut bytes).unwrap();
+    bytes[..4].copy_from_slice(b"NOPE");
+    let error = Header::read(&mut Cursor::new(bytes.clone())).unwrap_err();
+    assert!(error.to_string().contains("magic"), "{}", error);
+
+    let dir = TempDir::new("bad_magic_is_rejected");
+    std::fs::write(dir.path().join(save::LEVEL_FILE), bytes).unwrap();
+    assert!(World::open(dir.path()).is_err());
+}
+
+#[test]
+fn unknown_version_is_rejected() {
+    let mut bytes = Vec::new();
+    header(save::FORMAT_VERSION + 1).write(&mut bytes).unwrap();
+    let error = Header::read(&mut Cursor::new(bytes.clone())).unwrap_err();
+    assert!(error.to_string().contains("version"), "{}", error);
+
+    let dir = TempDir::new("unknown_version_is_rejected");
+    std::fs::write(dir.path().join(save::LEVEL_FILE), bytes).unwrap();
+    assert!(World::open(dir.path()).is_err());
+}
+
+#[test]
+fn unloaded_chunks_move_to_a_new_save() {
+    let first = TempDir::new("unloaded_chunks_move_to_a_new_save_first");
+    let second = TempDir::new("unloaded_chunks_move_to_a_new_save_second");
+    let mut world = small_world(17);
+    world.save(first.path()).unwrap();
+
+    let mut opened = World::open(first.path()).unwrap();
+    let coords = ChunkPos::new(0, 0);
+    assert!(opened.load_chunk(coords).unwrap());
+    opened
+        .chunk_mut(coords)
+        .unwrap()
+        .set_local(LocalPos::new(2, 3, 4), BlockKind::named("dirt"));
+    let changed = opened.chunk(coords).unwrap().clone();
+    // the other eight chunks stay unloaded
+    opened.save(second.path()).unwrap();
+
+    let loaded = World::load(second.path()).unwrap();
+    assert_eq!(loaded.chunks().len(), 9);
+    assert!(loaded.chunk(coords).unwrap() == &changed);
+    for (pos, blocks) in world.chunks() {
+        if *pos != coords {
+            assert!(loaded.chunk(*pos).unwrap() == blocks);
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use core::block::BlockKind;
+use core::chunk::storage::ChunkStorage;
+
+/// A deterministic block kind for every voxel, `kinds` distinct ones
+fn pattern(i: usize, kinds: u16) -> BlockKind {
+    BlockKind((i * 7 % kinds as usize) as u16)
+}
+
+#[test]
+fn single_kind_keeps_no_data() {
+    let storage = ChunkStorage::filled(BlockKind(3));
+    assert_eq!(storage.bits(), 0);
+    assert_eq!(storage.palette(), &[BlockKind(3)]);
+    assert!(storage.iter().all(|kind| kind == BlockKind(3)));
+}
+
+#[test]
+fn palette_growth_widens_indices() {
+    let mut storage = ChunkStorage::default();
+    let last = ChunkStorage::LEN - 1;
+    // (new kind, expected bits once the palette holds it)
+    let steps = [
+        (1, 1),
+        (2, 2),
+        (3, 2),
+        (4, 3),
+        (5, 3),
+        (6, 3),
+        (7, 3),
+        (8, 4),
+    ];
+    for (n, (id, bits)) in steps.into_iter().enumerate() {
+        storage.set(n + 1, BlockKind(id));
+        assert_eq!(storage.bits(), bits, "palette of {} kinds", id + 1);
+        assert_eq!(storage.palette().len(), id as usize + 1);
+        // every earlier write survives the repack
+        assert_eq!(storage.get(0), BlockKind::AIR);
+        for (m, (id, _)) in steps.iter().enumerate().take(n + 1) {
+            assert_eq!(storage.get(m + 1), BlockKind(*id));
+        }
+        assert_eq!(storage.get(last), BlockKind::AIR);
+    }
+}
+
+#[test]
+fn repacking_keeps_every_voxel() {
+    // 2 kinds is 1 bit, 3 is 2 bits and 5 is 3 bits, which leaves one unused
+    // bit in every word
+    for kinds in [2, 3, 5, 9] {
+        let mut storage = ChunkStorage::default();
+        for i in 0..ChunkStorage::LEN {
+            storage.set(i, pattern(i, kinds));
+        }
+        for i in 0..ChunkStorage::LEN {
+            assert_eq!(
+                storage.get(i),
+                pattern(i, kinds),
+                "voxel {} of {}",
+                i,
+                kinds
+            );
+        }
+    }
+}
+
+#[test]
+fn three_bit_indices_across_word_boundaries() {
+    let mut storage = ChunkStorage::default();
+    for id in 1..5 {
+        storage.set(id as usize, BlockKind(id));
+    }
+    assert_eq!(storage.bits(), 3);
+    // 21 indices fit into a word, voxels 20..22 straddle the first boundary
+    for i in 18..24 {
+        storage.set(i, BlockKind(4));
+        storage.set(i + 21, BlockKind(3));
+    }
+    for i in 18..24 {
+        assert_eq!(storage.get(i), BlockKind(4));
+        assert_eq!(storage.get(i + 21), BlockKind(3));
+    }
+    assert_eq!(storage.get(17), BlockKind::AIR);
+    assert_eq!(storage.get(24), BlockKind::AIR);
+    assert_eq!(storage.get(45), BlockKind::AIR);
+}
+
+#[test]
+fn overwrite_after_resize() {
+    let mut storage = ChunkStorage::default();
+    for i in 0..ChunkStorage::LEN {
+        storage.set(i, pattern(i, 5));
+    }
+    assert_eq!(storage.bits(), 3);
+    for i in (0..ChunkStorage::LEN).step_by(3) {
+        storage.set(i, BlockKind::AIR);
+    }
+    for i in 0..ChunkStorage::LEN {
+        let expected = if i % 3 == 0 {
+            BlockKind::AIR
+        } else {
+            pattern(i, 5)
+        };
+        assert_eq!(storage.get(i), expected);
+    }
+}
+
+#[test]
+fn from_slice_matches_blocks() {
+    let blocks: Vec<_> = (0..ChunkStorage::LEN).map(|i| pattern(i, 5)).collect();
+    let storage = ChunkStorage::from(blocks.as_slice());
+    assert_eq!(storage.to_vec(), blocks);
+    assert!(storage == ChunkStorage::from(blocks.as_slice()));
+}
+
+#[test]
+fn levels_reset_with_the_block() {
+    let mut storage = ChunkStorage::default();
+    let water = BlockKind(13);
+    storage.set(7, water);
+    storage.set_level(7, 3);
+    storage.set_level(9, 5);
+    assert_eq!(storage.level(7), 3);
+    assert_eq!(storage.levels().collect::<Vec<_>>(), vec![(7, 3), (9, 5)]);
+
+    let mut source = storage.clone();
+    source.set_level(7, 0);
+    assert!(source != storage);
+    // a new block of a voxel has level 0, even the same block
+    storage.set(7, water);
+    assert_eq!(storage.level(7), 0);
+    assert!(source == storage);
+    storage.set(9, BlockKind::AIR);
+    assert_eq!(storage.levels().count(), 0);
+}
@@ -0,0 +1,198 @@
+mod common;
+
+use common::{flat_world, run, surface, FLOOR};
+use core::block::BlockKind;
+use core::coords::{BlockPos, ChunkPos, LocalPos};
+use core::world::fluid::Fluid;
+use core::world::tick::TickLoop;
+use core::world::World;
+use std::time::Duration;
+
+fn count(world: &World, kind: BlockKind) -> usize {
+    world
+        .chunks()
+        .values()
+        .map(|blocks| blocks.iter().filter(|block| *block == kind).count())
+        .sum()
+}
+
+#[test]
+fn advance_runs_fixed_ticks() {
+    let mut world = flat_world("stone", 1);
+    let mut ticks = TickLoop::new(1);
+    ticks.advance(&mut world, Duration::from_millis(120));
+    assert_eq!(ticks.ticks(), 2);
+    // the remaining 20 ms add up with the next frame
+    ticks.advance(&mut world, Duration::from_millis(30));
+    assert_eq!(ticks.ticks(), 3);
+    ticks.advance(&mut world, Duration::from_millis(10));
+    assert_eq!(ticks.ticks(), 3);
+    // a long frame does not run more than the maximum
+    ticks.advance(&mut world, Duration::from_secs(10));
+    assert_eq!(ticks.ticks(), 3 + TickLoop::MAX_TICKS_PER_FRAME as u64);
+    ticks.advance(&mut world, Duration::from_millis(50));
+    assert_eq!(ticks.ticks(), 4 + TickLoop::MAX_TICKS_PER_FRAME as u64);
+}
+
+#[test]
+fn scheduled_ticks_skip_changed_blocks() {
+    let mut world = flat_world("stone", 1);
+    let mut ticks = TickLoop::new(1);
+    ticks.random_ticks = 0;
+    let sand = BlockKind::named("sand");
+    let pos = BlockPos::new(3, 20, 3);
+    world.set_block(pos, sand);
+    ticks.schedule(pos, sand, 5);
+    assert_eq!(ticks.pending(), 1);
+    run(&mut ticks, &mut world, 4);
+    assert_eq!(world.get_block(pos), Some(sand));
+    run(&mut ticks, &mut world, 1);
+    assert_eq!(world.get_block(pos), Some(BlockKind::AIR));
+    assert_eq!(world.get_block(pos.offset(0, -1, 0)), Some(sand));
+
+    let gravel = BlockKind::named("gravel");
+    let pos = BlockPos::new(8, 20, 8);
+    world.set_block(pos, sand);
+    ticks.schedule(pos, sand, 5);
+    world.set_block(pos, gravel);
+    run(&mut ticks, &mut world, 5);
+    assert_eq!(world.get_block(pos), Some(gravel));
+}
+
+#[test]
+fn sand_falls_onto_the_floor() {
+    let mut world = flat_world("stone", 1);
+    let mut ticks = TickLoop::new(1);
+    let sand = BlockKind::named("sand");
+    // a column across the border of chunk (0, 0) and (-1, 0)
+    for y in 20..23 {
+        world.set_block(BlockPos::new(-1, y, 4), sand);
+    }
+    world.set_block(BlockPos::new(0, 25, 4), sand);
+    ticks.block_changed(&world, BlockPos::new(-1, 20, 4));
+    ticks.block_changed(&world, BlockPos::new(0, 25, 4));
+    let changed = run(&mut ticks, &mut world, 100);
+    assert_eq!(ticks.pending(), 0);
+    for y in 0..3 {
+        assert_eq!(world.get_block(surface(-1, 4).offset(0, y, 0)), Some(sand));
+    }
+    assert_eq!(
+        world.get_block(surface(-1, 4).offset(0, 3, 0)),
+        Some(BlockKind::AIR)
+    );
+    assert_eq!(world.get_block(surface(0, 4)), Some(sand));
+    assert_eq!(count(&world, sand), 4);
+    assert_eq!(changed, vec![ChunkPos::new(-1, 0), ChunkPos::new(0, 0)]);
+}
+
+#[test]
+fn sand_falls_when_its_support_is_removed() {
+    let mut world = flat_world("stone", 1);
+    let mut ticks = TickLoop::new(1);
+    let sand = BlockKind::named("sand");
+    let support = surface(5, 5).offset(0, 3, 0);
+    world.set_block(support, BlockKind::named("stone"));
+    world.set_block(support.offset(0, 1, 0), sand);
+    ticks.block_changed(&world, support.offset(0, 1, 0));
+    run(&mut ticks, &mut world, 20);
+    assert_eq!(world.get_block(support.offset(0, 1, 0)), Some(sand));
+
+    world.set_block(support, BlockKind::AIR);
+    ticks.block_changed(&world, support);
+    run(&mut ticks, &mut world, 20);
+    assert_eq!(world.get_block(surface(5, 5)), Some(sand));
+}
+
+#[test]
+fn sand_sinks_through_water() {
+    let mut world = flat_world("stone", 1);
+    let mut ticks = TickLoop::new(1);
+    ticks.random_ticks = 0;
+    ticks.fluids.place(&mut world, surface(8, 8), Fluid::Water);
+    run(&mut ticks, &mut world, 100);
+    let sand = BlockKind::named("sand");
+    world.set_block(surface(8, 8).offset(0, 2, 0), sand);
+    ticks.block_changed(&world, surface(8, 8).offset(0, 2, 0));
+    run(&mut ticks, &mut world, 100);
+    assert_eq!(world.get_block(surface(8, 8)), Some(sand));
+    // without its source the water dries up
+    assert_eq!(count(&world, BlockKind::named("water")), 0);
+}
+
+#[test]
+fn grass_spreads_onto_uncovered_dirt() {
+    let mut world = flat_world("dirt", 0);
+    let mut ticks = TickLoop::new(3);
+    ticks.random_ticks = 64;
+    let grass = BlockKind::named("grass");
+    let top = surface(8, 8).offset(0, -1, 0);
+    world.set_block(top, grass);
+    // covered dirt stays dirt
+    for x in 10..13 {
+        world.set_block(surface(x, 8), BlockKind::named("stone"));
+    }
+    run(&mut ticks, &mut world, 2_000);
+    assert!(count(&world, grass) > 20);
+    assert_eq!(
+        world.get_block(top.offset(3, 0, 0)),
+        Some(BlockKind::named("dirt"))
+    );
+    assert_eq!(
+        world.get_block(top.offset(0, -1, 0)),
+        Some(BlockKind::named("dirt"))
+    );
+}
+
+#[test]
+fn covered_grass_turns_into_dirt() {
+    let mut world = flat_world("grass", 0);
+    let mut ticks = TickLoop::new(3);
+    ticks.random_ticks = 64;
+    for x in 0..16 {
+        for z in 0..16 {
+            world.set_block(surface(x, z), BlockKind::named("stone"));
+        }
+    }
+    run(&mut ticks, &mut world, 200);
+    let dirt = LocalPos::all()
+        .filter(|local| local.y == FLOOR)
+        .filter(|local| world.get_block((*local).into()) == Some(BlockKind::named("dirt")))
+        .count();
+    assert!(dirt > 200, "{}", dirt);
+}
+
+#[test]
+fn ticks_are_deterministic() {
+    let simulate = || {
+        let mut world = flat_world("dirt", 1);
+        let mut ticks = TickLoop::new(11);
+        world.set_block(surface(2, 2).offset(0, -1, 0), BlockKind::named("grass"));
+        world.set_block(surface(9, 9).offset(0, 4, 0), BlockKind::named("sand"));
+        ticks.block_changed(&world, surface(9, 9).offset(0, 4, 0));
+        let changed = run(&mut ticks, &mut world, 500);
+        let blocks: Vec<_> = [ChunkPos::new(-1, 0), ChunkPos::new(0, 0)]
+            .iter()
+            .map(|pos| world.chunk(*pos).unwrap().to_vec())
+            .collect();
+        (changed, blocks)
+    };
+    assert_eq!(simulate(), simulate());
+}
+
+#[test]
+fn sand_falls_when_the_water_below_dries_up() {
+    let mut world = flat_world("stone", 1);
+    let mut ticks = TickLoop::new(1);
+    ticks.random_ticks = 0;
+    let sand = BlockKind::named("sand");
+    // flowing water without a source, with sand resting on it
+    let water = surface(6, 6);
+    world.set_block(water, Fluid::Water.block());
+    world.set_level(water, 3);
+    world.set_block(water.offset(0, 1, 0), sand);
+    ticks.fluids.schedule(water, 1);
+    run(&mut ticks, &mut world, 20);
+    assert_eq!(count(&world, BlockKind::named("water")), 0);
+    assert_eq!(world.get_block(water), Some(sand));
+    assert_eq!(world.get_block(water.offset(0, 1, 0)), Some(BlockKind::AIR));
+}
@@ -0,0 +1,88 @@
+use core::chunk::Chunk;
+use core::vertex::ChunkVertex;
+
+const WIDTH: u32 = Chunk::WIDTH as u32;
+const HEIGHT: u32 = Chunk::HEIGHT as u32;
+const UV_MAX: u32 = 127;
+
+fn assert_round_trip(
+    position: [u32; 3],
+    face: usize,
+    tex_coords: [u32; 2],
+    material: usize,
+    ao: u8,
+    lowered: bool,
+) {
+    let vertex = ChunkVertex::new(position, face, tex_coords, material, ao).with_lowered(lowered);
+    assert_eq!(vertex.position(), position);
+    assert_eq!(vertex.face(), face);
+    assert_eq!(vertex.tex_coords(), tex_coords);
+    assert_eq!(vertex.material(), material);
+    assert_eq!(vertex.ao(), ao);
+    assert_eq!(vertex.lowered(), lowered);
+}
+
+#[test]
+fn maximum_values_round_trip() {
+    assert_round_trip(
+        [WIDTH, HEIGHT, WIDTH],
+        5,
+        [UV_MAX, UV_MAX],
+        u16::MAX as usize,
+        3,
+        true,
+    );
+}
+
+#[test]
+fn zero_values_round_trip() {
+    assert_round_trip([0, 0, 0], 0, [0, 0], 0, 0, false);
+    assert_eq!(ChunkVertex::new([0, 0, 0], 0, [0, 0], 0, 0).data, [0, 0]);
+}
+
+#[test]
+fn fields_do_not_overlap() {
+    // a single field at its maximum next to zeros everywhere else
+    assert_round_trip([WIDTH, 0, 0], 0, [0, 0], 0, 0, false);
+    assert_round_trip([0, HEIGHT, 0], 0, [0, 0], 0, 0, false);
+    assert_round_trip([0, 0, WIDTH], 0, [0, 0], 0, 0, false);
+    assert_round_trip([0, 0, 0], 5, [0, 0], 0, 0, false);
+    assert_round_trip([0, 0, 0], 0, [0, 0], 0, 3, false);
+    assert_round_trip([0, 0, 0], 0, [0, 0], 0, 0, true);
+    assert_round_trip([0, 0, 0], 0, [UV_MAX, 0], 0, 0, false);
+    assert_round_trip([0, 0, 0], 0, [0, UV_MAX], 0, 0, false);
+    assert_round_trip([0, 0, 0], 0, [0, 0], u16::MAX as usize, 0, false);
+}
+
+#[test]
+fn lowered_flag_toggles() {
+    let vertex = ChunkVertex::new([3, 4, 5], 2, [6, 7], 8, 1);
+    let lowered = vertex.with_lowered(true);
+    assert!(lowered.lowered());
+    assert_eq!(lowered.with_lowered(false), vertex);
+}
+
+#[test]
+#[should_panic]
+fn position_outside_of_chunk_panics() {
+    ChunkVertex::new([0, HEIGHT + 1, 0], 0, [0, 0], 0, 0);
+}
+
+#[test]
+#[should_panic]
+fn wide_texture_coordinates_panic() {
+    ChunkVertex::new([0, 0, 0], 0, [UV_MAX + 1, 0], 0, 0);
+}
+
+#[test]
+#[should_panic]
+fn material_overflow_panics() {
+    ChunkVertex::new([0, 0, 0], 0, [0, 0], u16::MAX as usize + 1, 0);
+}
+
+#[test]
+fn shaders_get_the_liquid_drop() {
+    let source = ChunkVertex::shader_source("fn main() {}");
+    assert!(source.starts_with("let LIQUID_DROP: f32 = 0.125;\n"));
+    assert!(source.ends_with("fn main() {}"));
+}
@@ -0,0 +1,116 @@
+mod common;
+
+use std::collections::HashMap;
+
+use common::stone;
+use core::block::BlockKind;
+use core::chunk::storage::ChunkStorage;
+use core::chunk::visibility::{visible_chunks, ChunkVisibility, Face};
+use core::chunk::Chunk;
+use core::coords::{BlockPos, ChunkPos, LocalPos};
+
+/// Solid stone with a tunnel of air along x at `y`, `z`
+fn tunnel(y: usize, z: usize) -> ChunkStorage {
+    let mut blocks = ChunkStorage::filled(stone());
+    for x in 0..Chunk::WIDTH {
+        blocks.set_local(LocalPos::new(x, y, z), BlockKind::AIR);
+    }
+    blocks
+}
+
+/// A row of chunks from x = -2 to x = 4, open but for the chunks in `solid`
+fn row(solid: &[i32]) -> HashMap<ChunkPos, ChunkVisibility> {
+    (-2..=4)
+        .map(|x| {
+            let visibility = if solid.contains(&x) {
+                ChunkVisibility::compute(&ChunkStorage::filled(stone()))
+            } else {
+                ChunkVisibility::open()
+            };
+            (ChunkPos::new(x, 0), visibility)
+        })
+        .collect()
+}
+
+fn camera() -> BlockPos {
+    BlockPos::new(8, 20, 8)
+}
+
+#[test]
+fn air_connects_every_face() {
+    let air = ChunkVisibility::compute(&ChunkStorage::filled(BlockKind::AIR));
+    assert_eq!(air, ChunkVisibility::open());
+}
+
+#[test]
+fn stone_connects_nothing() {
+    let visibility = ChunkVisibility::compute(&ChunkStorage::filled(stone()));
+    for section in 0..Chunk::SECTIONS {
+        for a in Face::ALL {
+            for b in Face::ALL {
+                assert!(!visibility.section(section).connects(a, b));
+            }
+        }
+    }
+}
+
+#[test]
+fn tunnel_connects_its_ends_only() {
+    let visibility = ChunkVisibility::compute(&tunnel(20, 8));
+    let section = visibility.section(1);
+    assert!(section.connects(Face::West, Face::East));
+    assert!(section.connects(Face::East, Face::West));
+    assert!(!section.connects(Face::West, Face::Up));
+    assert!(!section.connects(Face::North, Face::South));
+    assert!(!visibility.section(0).connects(Face::West, Face::East));
+}
+
+#[test]
+fn open_chunks_are_visible() {
+    let chunks = row(&[]);
+    let visible = visible_chunks(camera(), 8, |pos| chunks.get(&pos));
+    assert_eq!(visible.len(), chunks.len());
+}
+
+#[test]
+fn solid_chunks_hide_the_chunks_behind() {
+    let chunks = row(&[2]);
+    let visible = visible_chunks(camera(), 8, |pos| chunks.get(&pos));
+    for x in -2..=2 {
+        assert!(visible.contains(&ChunkPos::new(x, 0)), "{} is hidden", x);
+    }
+    for x in 3..=4 {
+        assert!(!visible.contains(&ChunkPos::new(x, 0)), "{} is visible", x);
+    }
+}
+
+#[test]
+fn tunnel_leads_through_solid_chunks() {
+    let mut chunks = row(&[2, 3]);
+    chunks.insert(
+        ChunkPos::new(2, 0),
+        ChunkVisibility::compute(&tunnel(20, 8)),
+    );
+    let visible = visible_chunks(camera(), 8, |pos| chunks.get(&pos));
+    assert!(visible.contains(&ChunkPos::new(2, 0)));
+    assert!(visible.contains(&ChunkPos::new(3, 0)));
+    assert!(!visible.contains(&ChunkPos::new(4, 0)));
+}
+
+#[test]
+fn traversal_stops_at_the_distance() {
+    let chunks = row(&[]);
+    let visible = visible_chunks(camera(), 1, |pos| chunks.get(&pos));
+    assert_eq!(visible.len(), 3);
+    assert!(!visible.contains(&ChunkPos::new(2, 0)));
+}
+
+#[test]
+fn camera_above_sees_the_tops() {
+    let chunks = row(&[-2, -1, 0, 1, 2, 3, 4]);
+    let above = BlockPos::new(8, Chunk::HEIGHT as i32 + 10, 8);
+    let visible = visible_chunks(above, 2, |pos| chunks.get(&pos));
+    assert_eq!(visible.len(), 5);
+    let inside = visible_chunks(camera(), 2, |pos| chunks.get(&pos));
+    assert_eq!(inside.len(), 3);
+}